@@ -10,7 +10,16 @@ pub mod routes {
     pub mod home;
 
     pub mod auth {
+        pub mod forgot_password;
         pub mod login;
+        pub mod login_mfa;
+        pub mod logout;
+        pub mod logout_all;
+        pub mod oidc_callback;
+        pub mod oidc_login;
+        pub mod refresh;
+        pub mod resend_verification;
+        pub mod reset_password;
         pub mod signup;
         pub mod verify;
     }
@@ -18,15 +27,80 @@ pub mod routes {
         pub mod calculator;
         pub mod create;
         pub mod delete;
+        pub mod proxy;
+        pub mod recreate;
         pub mod start;
         pub mod stop;
     }
     pub mod admin {
+        pub mod create_signup_invite;
+        pub mod delete_signup_invite;
+        pub mod get_audit_log;
+        pub mod get_signup_policy;
+        pub mod get_user;
+        pub mod get_user_containers;
+        pub mod grant_role;
+        pub mod revoke_role;
         pub mod set_credits;
+        pub mod set_plan;
+        pub mod set_signup_policy;
+        pub mod unlock_account;
     }
     pub mod account {
+        pub mod confirm_email;
+        pub mod delete;
         pub mod get_container;
         pub mod get_credits;
+        pub mod get_network_usage;
+        pub mod get_notifications;
+        pub mod get_orgs;
+        pub mod get_schedules;
+        pub mod get_secrets;
+        pub mod get_security;
+        pub mod get_tokens;
+        pub mod set_display_name;
+        pub mod set_email;
+        pub mod set_password;
+    }
+    pub mod events {
+        pub mod history;
+        pub mod live;
+    }
+    pub mod mfa {
+        pub mod confirm;
+        pub mod disable;
+        pub mod enroll;
+    }
+    pub mod org {
+        pub mod accept_invite;
+        pub mod create;
+        pub mod delete;
+        pub mod get_members;
+        pub mod invite;
+        pub mod remove_member;
+        pub mod set_role;
+        pub mod transfer_credits;
+    }
+    pub mod notifications {
+        pub mod set;
+    }
+    pub mod schedule {
+        pub mod create;
+        pub mod delete;
+    }
+    pub mod secrets {
+        pub mod delete;
+        pub mod set;
+    }
+    pub mod tokens {
+        pub mod create;
+        pub mod delete;
+    }
+    pub mod stack {
+        pub mod create;
+        pub mod delete;
+        pub mod start;
+        pub mod stop;
     }
     pub fn get_routes() -> Vec<Router> {
         vec![
@@ -34,21 +108,105 @@ pub mod routes {
             container::create::get_routes(),
             auth::signup::get_routes(),
             auth::verify::get_routes(),
+            auth::resend_verification::get_routes(),
             auth::login::get_routes(),
+            auth::login_mfa::get_routes(),
+            auth::refresh::get_routes(),
+            auth::logout::get_routes(),
+            auth::logout_all::get_routes(),
+            auth::oidc_login::get_routes(),
+            auth::oidc_callback::get_routes(),
+            auth::forgot_password::get_routes(),
+            auth::reset_password::get_routes(),
             account::get_container::get_routes(),
             admin::set_credits::get_routes(),
+            admin::set_plan::get_routes(),
+            admin::grant_role::get_routes(),
+            admin::revoke_role::get_routes(),
+            admin::get_user::get_routes(),
+            admin::get_user_containers::get_routes(),
+            admin::get_audit_log::get_routes(),
+            admin::get_signup_policy::get_routes(),
+            admin::set_signup_policy::get_routes(),
+            admin::create_signup_invite::get_routes(),
+            admin::delete_signup_invite::get_routes(),
+            admin::unlock_account::get_routes(),
             account::get_credits::get_routes(),
             container::delete::get_routes(),
             container::start::get_routes(),
             container::stop::get_routes(),
             container::calculator::get_routes(),
+            container::proxy::get_routes(),
+            container::recreate::get_routes(),
+            secrets::set::get_routes(),
+            secrets::delete::get_routes(),
+            account::get_secrets::get_routes(),
+            account::get_security::get_routes(),
+            account::get_network_usage::get_routes(),
+            schedule::create::get_routes(),
+            schedule::delete::get_routes(),
+            account::get_schedules::get_routes(),
+            notifications::set::get_routes(),
+            account::get_notifications::get_routes(),
+            events::history::get_routes(),
+            events::live::get_routes(),
+            mfa::enroll::get_routes(),
+            mfa::confirm::get_routes(),
+            mfa::disable::get_routes(),
+            org::create::get_routes(),
+            org::delete::get_routes(),
+            org::invite::get_routes(),
+            org::accept_invite::get_routes(),
+            org::set_role::get_routes(),
+            org::remove_member::get_routes(),
+            org::transfer_credits::get_routes(),
+            org::get_members::get_routes(),
+            account::get_orgs::get_routes(),
+            tokens::create::get_routes(),
+            tokens::delete::get_routes(),
+            account::get_tokens::get_routes(),
+            account::set_display_name::get_routes(),
+            account::set_password::get_routes(),
+            account::set_email::get_routes(),
+            account::confirm_email::get_routes(),
+            account::delete::get_routes(),
+            stack::create::get_routes(),
+            stack::start::get_routes(),
+            stack::stop::get_routes(),
+            stack::delete::get_routes(),
         ]
     }
 }
 pub mod utils {
+    pub mod account;
+    pub mod auth;
+    pub mod bandwidth;
+    pub mod config;
     pub mod container;
     pub mod db;
+    pub mod events;
+    pub mod hardening;
+    pub mod health;
+    pub mod idle;
+    pub mod lockout;
+    pub mod login_log;
+    pub mod mail;
+    pub mod mfa;
+    pub mod network;
+    pub mod oidc;
+    pub mod orgs;
+    pub mod rate_limit;
     pub mod res;
     pub mod resources;
+    pub mod roles;
+    pub mod schedule;
+    pub mod secrets;
+    pub mod session;
+    pub mod signup_policy;
+    pub mod stack;
+    pub mod storage;
+    pub mod tokens;
     pub mod validation;
+    pub mod verification;
+    pub mod webhook;
 }
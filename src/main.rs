@@ -6,16 +6,22 @@
 
 #![allow(dead_code)]
 
-use std::time::Duration;
+use std::net::SocketAddr;
 
-use axum::{error_handling::HandleErrorLayer, http::StatusCode, BoxError, Router};
-use dockify_backend::{routes, utils::db::create_db};
+use axum::{middleware, Router};
+use dockify_backend::{
+    routes,
+    utils::{
+        bandwidth, db::create_db, events, health, idle, rate_limit, schedule, storage, tokens,
+        verification,
+    },
+};
 use dotenvy::dotenv;
-use tower::{buffer::BufferLayer, limit::RateLimitLayer, ServiceBuilder};
 
 #[tokio::main]
 async fn main() {
     dotenv().ok();
+    rate_limit::load();
 
     let routes: Vec<Router> = routes::get_routes();
 
@@ -24,20 +30,24 @@ async fn main() {
         .fold(Router::new(), |router: Router, route: Router| {
             router.merge(route)
         })
-        .layer(
-            ServiceBuilder::new()
-                .layer(HandleErrorLayer::new(|err: BoxError| async move {
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        format!("Unhandled error: {}", err),
-                    )
-                }))
-                .layer(BufferLayer::new(1024))
-                .layer(RateLimitLayer::new(5, Duration::from_secs(5))),
-        );
+        .layer(middleware::from_fn(tokens::enforce_scopes))
+        .layer(middleware::from_fn(rate_limit::enforce));
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
     create_db().await;
+    tokio::spawn(storage::run_quota_checker());
+    tokio::spawn(bandwidth::run_usage_sampler());
+    tokio::spawn(idle::run_idle_detector());
+    tokio::spawn(schedule::run_scheduler());
+    tokio::spawn(health::run_health_monitor());
+    tokio::spawn(events::run_event_watcher());
+    tokio::spawn(verification::run_verification_cleanup());
+    tokio::spawn(rate_limit::run_bucket_cleanup());
     println!("Dockify backend is running...");
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
@@ -0,0 +1,50 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::utils::{auth::JsonBody, db, res::m_resp, validation};
+
+#[derive(Deserialize)]
+pub struct ConfirmBody {
+    token: String,
+}
+
+// The token from the email proves ownership of the address, so no login is needed
+pub async fn handler(JsonBody(body): JsonBody<ConfirmBody>) -> impl IntoResponse {
+    let now = Utc::now().timestamp();
+    let (username, email) = match db::take_email_change(&validation::hash_token(&body.token), now) {
+        Ok(Some(change)) => change,
+        Ok(None) => return m_resp(StatusCode::BAD_REQUEST, "Invalid or expired token."),
+        Err(err) => {
+            eprintln!(
+                "An error occurred while checking email change token: {}",
+                err
+            );
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    match db::set_email(&username, &email) {
+        Ok(true) => m_resp(StatusCode::OK, "Email has been changed."),
+        Ok(false) => m_resp(StatusCode::CONFLICT, "This email is already in use."),
+        Err(err) => {
+            eprintln!("An error occurred while changing email: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/account/email/confirm", post(handler))
+}
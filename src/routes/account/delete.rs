@@ -0,0 +1,124 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use serde::Deserialize;
+
+use crate::utils::{
+    account,
+    auth::{ClientIp, JsonBody, User},
+    db, mail,
+    orgs::OrgRole,
+    res::m_resp,
+    roles::Role,
+};
+
+#[derive(Deserialize)]
+pub struct DeleteBody {
+    password: String,
+}
+
+pub async fn handler(
+    User {
+        username, roles, ..
+    }: User,
+    ClientIp(ip): ClientIp,
+    JsonBody(body): JsonBody<DeleteBody>,
+) -> impl IntoResponse {
+    if let Err(err) = account::confirm_password(&username, &body.password, &ip) {
+        return err;
+    }
+    // Orgs can't be left without an owner
+    let orgs = match db::get_user_orgs(&username) {
+        Ok(orgs) => orgs,
+        Err(err) => {
+            eprintln!("Error while getting user's orgs: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    for org in orgs
+        .iter()
+        .filter(|org| OrgRole::parse(&org.role) == Some(OrgRole::Owner))
+    {
+        match db::count_org_owners(&org.org) {
+            Ok(1) => {
+                return m_resp(
+                    StatusCode::CONFLICT,
+                    format!(
+                        "Transfer ownership of {} or delete it before deleting your account.",
+                        org.org
+                    ),
+                )
+            }
+            Ok(_) => (),
+            Err(err) => {
+                eprintln!("Error while counting org owners: {}", err);
+                return m_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Please contact support for help.",
+                );
+            }
+        }
+    }
+    // Someone must be left who can grant roles again
+    if roles.contains(&Role::SuperAdmin) {
+        match db::count_role(Role::SuperAdmin.as_str()) {
+            Ok(count) if count <= 1 => {
+                return m_resp(
+                    StatusCode::CONFLICT,
+                    "Grant super admin to someone else before deleting your account.",
+                )
+            }
+            Ok(_) => (),
+            Err(err) => {
+                eprintln!("Error while counting super admins: {}", err);
+                return m_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Please contact support for help.",
+                );
+            }
+        }
+    }
+    let email = match db::get_user_info(&username) {
+        Ok((_, _, _, email)) => email,
+        Err(err) => {
+            eprintln!("An error occurred while getting user info: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    if let Err(err) = account::delete(&username, &email).await {
+        eprintln!(
+            "An error occurred while deleting account {}: {}",
+            username, err
+        );
+        return m_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Please contact support for help.",
+        );
+    }
+    println!("Deleted account {}.", username);
+    if let Err(err) = mail::send(
+        &email,
+        "Your Dockify account has been deleted",
+        format!(
+            "Hello {},\n\nYour Dockify account, its containers and its data have been deleted.\n\nThanks for using Dockify.",
+            username
+        ),
+    ) {
+        eprintln!("Error sending account deletion email: {}", err);
+    }
+    m_resp(StatusCode::OK, "Your account has been deleted.")
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/account/delete", post(handler))
+}
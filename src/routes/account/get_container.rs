@@ -5,21 +5,20 @@
 */
 
 #![warn(unused_variables)]
-use axum::{
-    body::Body, extract::Request, http::StatusCode, response::IntoResponse, routing::get, Router,
-};
+use axum::{http::StatusCode, response::IntoResponse, routing::get, Router};
 
 use crate::utils::{
+    auth::User,
     db,
+    orgs::OrgRole,
     res::{m_resp, Respond},
-    validation,
 };
 
-pub async fn handler(req: Request<Body>) -> impl IntoResponse {
-    let (validated, username) = validation::validate_request(&req.headers()).await;
-    if !validated {
-        return m_resp(StatusCode::UNAUTHORIZED, "Invalid token");
-    }
+pub async fn handler(user: User) -> impl IntoResponse {
+    let username = match user.account(OrgRole::Viewer) {
+        Ok(account) => account,
+        Err(err) => return err,
+    };
     Respond::Containers(
         StatusCode::OK,
         match db::get_user_containers(&username) {
@@ -4,22 +4,20 @@
     Find the LICENSE file in the root of this repository for more details.
 */
 
-use axum::{
-    body::Body, extract::Request, http::StatusCode, response::IntoResponse, routing::get, Router,
-};
+use axum::{http::StatusCode, response::IntoResponse, routing::get, Router};
 
 use crate::utils::{
+    auth::User,
     db,
+    orgs::OrgRole,
     res::{credits_resp, m_resp},
-    validation,
 };
 
-pub async fn handler(req: Request<Body>) -> impl IntoResponse {
-    let (validated, username) = validation::validate_request(&req.headers()).await;
-
-    if !validated {
-        return m_resp(StatusCode::UNAUTHORIZED, "Invalid token");
-    }
+pub async fn handler(user: User) -> impl IntoResponse {
+    let username = match user.account(OrgRole::Viewer) {
+        Ok(account) => account,
+        Err(err) => return err,
+    };
 
     let credits = match db::get_user_credits(&username) {
         Ok(c) => c,
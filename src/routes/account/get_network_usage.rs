@@ -0,0 +1,57 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::get, Router};
+
+use crate::utils::{
+    auth::User,
+    bandwidth, db,
+    orgs::OrgRole,
+    res::{m_resp, GenericResponse, Respond},
+};
+
+pub async fn handler(user: User) -> impl IntoResponse {
+    let username = match user.account(OrgRole::Viewer) {
+        Ok(account) => account,
+        Err(err) => return err,
+    };
+    let period = bandwidth::current_period();
+    let usage = db::get_user_network_usage(&username, &period).and_then(|(rx, tx)| {
+        Ok((
+            rx,
+            tx,
+            db::is_egress_throttled(&username, &period)?,
+            db::get_container_network_usage(&username, &period)?,
+        ))
+    });
+    match usage {
+        Ok((rx, tx, throttled, containers)) => Respond::Generic(
+            StatusCode::OK,
+            GenericResponse::NetworkUsage {
+                period,
+                rx,
+                tx,
+                included_transfer: bandwidth::policy_for_user(&username).included_transfer,
+                throttled,
+                containers,
+            },
+        ),
+        Err(err) => {
+            eprintln!(
+                "An error occurred while getting user's network usage: {}",
+                err
+            );
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/get_network_usage", get(handler))
+}
@@ -0,0 +1,36 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::get, Router};
+
+use crate::utils::{
+    auth::User,
+    db,
+    res::{m_resp, GenericResponse, Respond},
+};
+
+pub async fn handler(User { username, .. }: User) -> impl IntoResponse {
+    match db::get_notification_settings(&username) {
+        Ok((email, webhook)) => Respond::Generic(
+            StatusCode::OK,
+            GenericResponse::Notifications { email, webhook },
+        ),
+        Err(err) => {
+            eprintln!(
+                "An error occurred while getting user's notification settings: {}",
+                err
+            );
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/get_notifications", get(handler))
+}
@@ -0,0 +1,30 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::get, Router};
+
+use crate::utils::{
+    auth::User,
+    db,
+    res::{m_resp, GenericResponse, Respond},
+};
+
+pub async fn handler(User { username, .. }: User) -> impl IntoResponse {
+    match db::get_user_orgs(&username) {
+        Ok(orgs) => Respond::Generic(StatusCode::OK, GenericResponse::Orgs { orgs }),
+        Err(err) => {
+            eprintln!("An error occurred while getting user's orgs: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/get_orgs", get(handler))
+}
@@ -0,0 +1,35 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::get, Router};
+
+use crate::utils::{
+    auth::User,
+    db,
+    orgs::OrgRole,
+    res::{m_resp, GenericResponse, Respond},
+};
+
+pub async fn handler(user: User) -> impl IntoResponse {
+    let username = match user.account(OrgRole::Viewer) {
+        Ok(account) => account,
+        Err(err) => return err,
+    };
+    match db::get_user_schedules(&username) {
+        Ok(schedules) => Respond::Generic(StatusCode::OK, GenericResponse::Schedules { schedules }),
+        Err(err) => {
+            eprintln!("An error occurred while getting user's schedules: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/get_schedules", get(handler))
+}
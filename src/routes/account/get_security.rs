@@ -0,0 +1,46 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use chrono::Utc;
+
+use crate::utils::{
+    auth::User,
+    db,
+    res::{m_resp, GenericResponse, Respond},
+    validation,
+};
+
+const RECENT_LOGINS: i64 = 50;
+
+// Recent sign-in attempts and the sessions that are still active
+pub async fn handler(User { username, .. }: User, headers: HeaderMap) -> impl IntoResponse {
+    let current = validation::request_session(&headers).await;
+    let logins = db::get_login_events(&username, RECENT_LOGINS);
+    let sessions = db::get_active_sessions(&username, current.as_deref(), Utc::now().timestamp());
+    match (logins, sessions) {
+        (Ok(logins), Ok(sessions)) => Respond::Generic(
+            StatusCode::OK,
+            GenericResponse::Security { logins, sessions },
+        ),
+        (Err(err), _) | (_, Err(err)) => {
+            eprintln!("An error occurred while getting account security: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/account/security", get(handler))
+}
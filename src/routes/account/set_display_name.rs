@@ -0,0 +1,47 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::{JsonBody, User},
+    db,
+    res::m_resp,
+};
+
+#[derive(Deserialize)]
+pub struct DisplayNameBody {
+    display_name: String,
+}
+
+pub async fn handler(
+    User { username, .. }: User,
+    JsonBody(body): JsonBody<DisplayNameBody>,
+) -> impl IntoResponse {
+    let name = body.display_name.trim();
+    if name.is_empty() || name.len() > 32 || !name.chars().all(|c| c.is_ascii_graphic()) {
+        return m_resp(
+            StatusCode::BAD_REQUEST,
+            "Display names must be 1 to 32 characters without spaces.",
+        );
+    }
+    match db::set_display_name(&username, name) {
+        Ok(true) => m_resp(StatusCode::OK, "Display name has been changed."),
+        Ok(false) => m_resp(StatusCode::CONFLICT, "This display name is taken."),
+        Err(err) => {
+            eprintln!("An error occurred while setting display name: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/account/display_name", post(handler))
+}
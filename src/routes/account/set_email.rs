@@ -0,0 +1,123 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+
+use crate::utils::{
+    account,
+    auth::{ClientIp, JsonBody, User},
+    db, mail,
+    res::m_resp,
+    signup_policy, validation,
+};
+
+const CHANGE_EXPIRY_MINUTES: i64 = 60;
+
+#[derive(Deserialize)]
+pub struct EmailBody {
+    email: String,
+    password: String,
+}
+
+// The new address only replaces the old one once the link sent to it is opened
+pub async fn handler(
+    User { username, .. }: User,
+    ClientIp(ip): ClientIp,
+    JsonBody(body): JsonBody<EmailBody>,
+) -> impl IntoResponse {
+    let email = body.email.trim().to_lowercase();
+    if !validation::validate_email(&email) {
+        return m_resp(StatusCode::BAD_REQUEST, "Invalid email.");
+    }
+    if let Err(err) = account::confirm_password(&username, &body.password, &ip) {
+        return err;
+    }
+    let current_email = match db::get_user_info(&username) {
+        Ok((_, _, _, current_email)) => current_email,
+        Err(err) => {
+            eprintln!("An error occurred while getting user info: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    if email == current_email {
+        return m_resp(StatusCode::BAD_REQUEST, "This is already your email.");
+    }
+    match db::check_exists(&email, "email", "users") {
+        Ok(false) => (),
+        Ok(true) => return m_resp(StatusCode::CONFLICT, "This email is already in use."),
+        Err(err) => {
+            eprintln!("An error occurred while checking email: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    }
+    let allowed = signup_policy::get()
+        .map(|policy| signup_policy::check_email(&policy, &email).map_err(|r| r.message()));
+    match allowed {
+        Ok(Ok(())) => (),
+        Ok(Err(message)) => return m_resp(StatusCode::FORBIDDEN, message),
+        Err(err) => {
+            eprintln!("Error while getting signup policy: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    }
+    let token = validation::generate_token();
+    let expires = Utc::now() + Duration::minutes(CHANGE_EXPIRY_MINUTES);
+    if let Err(err) = db::insert_email_change(
+        &username,
+        &email,
+        &validation::hash_token(&token),
+        expires.timestamp(),
+    ) {
+        eprintln!("An error occurred while inserting email change: {}", err);
+        return m_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Please contact support for help.",
+        );
+    }
+    if let Err(err) = mail::send(
+        &email,
+        "Confirm your new Dockify email",
+        format!(
+            "Hello {},\n\nConfirm this address as the new email of your Dockify account here, the link expires in {} minutes:\nhttps://dockify.xyz/confirm-email?token={}\n\nIf you didn't request this, you can ignore this email.",
+            username, CHANGE_EXPIRY_MINUTES, token
+        ),
+    ) {
+        eprintln!("Error sending email change confirmation: {}", err);
+        return m_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Please contact support for help.",
+        );
+    }
+    if let Err(err) = mail::send(
+        &current_email,
+        "Your Dockify email is being changed",
+        format!(
+            "Hello {},\n\nA change of your Dockify account's email to {} was requested from {}. It takes effect once confirmed from the new address.\n\nIf this wasn't you, reset your password and contact support.",
+            username, email, ip
+        ),
+    ) {
+        eprintln!("Error sending email change notice: {}", err);
+    }
+    m_resp(
+        StatusCode::ACCEPTED,
+        "A confirmation link has been sent to the new email.",
+    )
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/account/email", post(handler))
+}
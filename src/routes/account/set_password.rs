@@ -0,0 +1,77 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::post,
+    Router,
+};
+use serde::Deserialize;
+
+use crate::utils::{
+    account,
+    auth::{ClientIp, JsonBody, User},
+    db, mail,
+    res::m_resp,
+    validation,
+};
+
+#[derive(Deserialize)]
+pub struct PasswordBody {
+    current_password: String,
+    new_password: String,
+}
+
+pub async fn handler(
+    User { username, .. }: User,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    JsonBody(body): JsonBody<PasswordBody>,
+) -> impl IntoResponse {
+    if body.new_password.is_empty() {
+        return m_resp(StatusCode::BAD_REQUEST, "Password can't be empty.");
+    }
+    if let Err(err) = account::confirm_password(&username, &body.current_password, &ip) {
+        return err;
+    }
+    let hash = match validation::hash_password(body.new_password) {
+        Ok(hash) => hash,
+        Err(err) => {
+            eprintln!("An error occurred while hashing: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    // Every other session is logged out, API tokens keep working
+    let current = validation::request_session(&headers).await;
+    if let Err(err) = db::change_password(&username, &hash, current.as_deref()) {
+        eprintln!("An error occurred while changing password: {}", err);
+        return m_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Please contact support for help.",
+        );
+    }
+    if let Ok((_, _, _, email)) = db::get_user_info(&username) {
+        if let Err(err) = mail::send(
+            &email,
+            "Your Dockify password was changed",
+            format!(
+                "Hello {},\n\nThe password of your Dockify account was just changed from {} and your other sessions were signed out.\n\nIf this wasn't you, reset your password and contact support.",
+                username, ip
+            ),
+        ) {
+            eprintln!("Error sending password change email: {}", err);
+        }
+    }
+    m_resp(StatusCode::OK, "Password has been changed.")
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/account/password", post(handler))
+}
@@ -0,0 +1,83 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::JsonBody,
+    db,
+    res::{m_resp, GenericResponse, Respond},
+    roles::{self, Authorized, ManageSignups},
+    validation,
+};
+
+const MAX_USES: i64 = 1000;
+
+fn default_uses() -> i64 {
+    1
+}
+#[derive(Deserialize)]
+pub struct InviteBody {
+    #[serde(default = "default_uses")]
+    max_uses: i64,
+    // Codes without an expiry stay valid until used up or deleted
+    expires_in_days: Option<i64>,
+}
+
+pub async fn handler(
+    auth: Authorized<ManageSignups>,
+    JsonBody(body): JsonBody<InviteBody>,
+) -> impl IntoResponse {
+    if !(1..=MAX_USES).contains(&body.max_uses) {
+        return m_resp(
+            StatusCode::BAD_REQUEST,
+            format!("max_uses must be between 1 and {}.", MAX_USES),
+        );
+    }
+    let now = Utc::now();
+    let expires = match body.expires_in_days {
+        Some(days) if (1..=365).contains(&days) => Some((now + Duration::days(days)).timestamp()),
+        Some(_) => {
+            return m_resp(
+                StatusCode::BAD_REQUEST,
+                "expires_in_days must be between 1 and 365.",
+            )
+        }
+        None => None,
+    };
+    let code = validation::generate_token();
+    match db::insert_signup_invite(
+        &validation::hash_token(&code),
+        &auth.username,
+        body.max_uses,
+        expires,
+        now.timestamp(),
+    ) {
+        Ok(id) => {
+            roles::audit(
+                &auth.username,
+                "create_signup_invite",
+                "signups",
+                Some(&id.to_string()),
+            );
+            // The code is only shown once
+            Respond::Generic(StatusCode::OK, GenericResponse::InviteCode { id, code })
+        }
+        Err(err) => {
+            eprintln!("Error while inserting signup invite: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/admin/create_signup_invite", post(handler))
+}
@@ -0,0 +1,49 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::JsonBody,
+    db,
+    res::m_resp,
+    roles::{self, Authorized, ManageSignups},
+};
+
+#[derive(Deserialize)]
+pub struct InviteId {
+    id: i64,
+}
+
+pub async fn handler(
+    auth: Authorized<ManageSignups>,
+    JsonBody(body): JsonBody<InviteId>,
+) -> impl IntoResponse {
+    match db::delete_signup_invite(body.id) {
+        Ok(true) => {
+            roles::audit(
+                &auth.username,
+                "delete_signup_invite",
+                "signups",
+                Some(&body.id.to_string()),
+            );
+            m_resp(StatusCode::OK, "")
+        }
+        Ok(false) => m_resp(StatusCode::NOT_FOUND, "No invite found with this id."),
+        Err(err) => {
+            eprintln!("Error while deleting signup invite: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/admin/delete_signup_invite", post(handler))
+}
@@ -0,0 +1,50 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{extract::Query, http::StatusCode, response::IntoResponse, routing::get, Router};
+use serde::Deserialize;
+
+use crate::utils::{
+    db,
+    res::{m_resp, GenericResponse, Respond},
+    roles::{Authorized, ViewAuditLog},
+};
+
+const MAX_ENTRIES: i64 = 200;
+
+fn default_limit() -> i64 {
+    50
+}
+#[derive(Deserialize)]
+pub struct AuditQuery {
+    // Only entries about this user
+    target: Option<String>,
+    before: Option<i64>,
+    #[serde(default = "default_limit")]
+    limit: i64,
+}
+
+pub async fn handler(
+    _auth: Authorized<ViewAuditLog>,
+    Query(query): Query<AuditQuery>,
+) -> impl IntoResponse {
+    let limit = query.limit.clamp(1, MAX_ENTRIES);
+    let target = query.target.map(|target| target.to_lowercase());
+    match db::get_audit_log(target.as_deref(), query.before, limit) {
+        Ok(entries) => Respond::Generic(StatusCode::OK, GenericResponse::AuditLog { entries }),
+        Err(err) => {
+            eprintln!("An error occurred while getting the audit log: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/admin/audit_log", get(handler))
+}
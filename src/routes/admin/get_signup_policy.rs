@@ -0,0 +1,44 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::get, Router};
+
+use crate::utils::{
+    db,
+    res::{m_resp, GenericResponse, Respond},
+    roles::{Authorized, ManageSignups},
+    signup_policy,
+};
+
+pub async fn handler(_auth: Authorized<ManageSignups>) -> impl IntoResponse {
+    let policy = match signup_policy::get() {
+        Ok(policy) => policy,
+        Err(err) => {
+            eprintln!("Error while getting signup policy: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    match db::get_signup_invites() {
+        Ok(invites) => Respond::Generic(
+            StatusCode::OK,
+            GenericResponse::SignupPolicy { policy, invites },
+        ),
+        Err(err) => {
+            eprintln!("Error while getting signup invites: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/admin/signup_policy", get(handler))
+}
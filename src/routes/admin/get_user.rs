@@ -0,0 +1,57 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{extract::Query, http::StatusCode, response::IntoResponse, routing::get, Router};
+use serde::Deserialize;
+
+use crate::utils::{
+    db,
+    res::{m_resp, GenericResponse, Respond},
+    roles::{self, Authorized, ViewUsers},
+};
+
+#[derive(Deserialize)]
+pub struct UserQuery {
+    // Username or email
+    pub user: String,
+}
+
+pub async fn handler(
+    _auth: Authorized<ViewUsers>,
+    Query(query): Query<UserQuery>,
+) -> impl IntoResponse {
+    let user = match db::get_user_details(&query.user.to_lowercase()) {
+        Ok(Some(user)) => user,
+        Ok(None) => return m_resp(StatusCode::NOT_FOUND, "User not found."),
+        Err(err) => {
+            eprintln!("Error while getting user details: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    match roles::user_roles(&user.username) {
+        Ok(roles) => Respond::Generic(
+            StatusCode::OK,
+            GenericResponse::User {
+                user: Box::new(user),
+                roles,
+            },
+        ),
+        Err(err) => {
+            eprintln!("Error while getting user roles: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/admin/get_user", get(handler))
+}
@@ -0,0 +1,37 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{extract::Query, http::StatusCode, response::IntoResponse, routing::get, Router};
+
+use crate::{
+    routes::admin::get_user::UserQuery,
+    utils::{
+        db,
+        res::{m_resp, Respond},
+        roles::{Authorized, ViewContainers},
+    },
+};
+
+pub async fn handler(
+    _auth: Authorized<ViewContainers>,
+    Query(query): Query<UserQuery>,
+) -> impl IntoResponse {
+    match db::get_user_containers(&query.user.to_lowercase()) {
+        Ok(containers) => Respond::Containers(StatusCode::OK, containers),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Respond::Containers(StatusCode::OK, vec![]),
+        Err(err) => {
+            eprintln!("Error while getting user containers: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/admin/get_user_containers", get(handler))
+}
@@ -0,0 +1,74 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::JsonBody,
+    db,
+    res::m_resp,
+    roles::{self, Authorized, ManageRoles, Role},
+};
+
+#[derive(Deserialize)]
+pub struct RoleBody {
+    pub username: String,
+    pub role: Role,
+}
+
+pub async fn handler(
+    auth: Authorized<ManageRoles>,
+    JsonBody(body): JsonBody<RoleBody>,
+) -> impl IntoResponse {
+    let username = body.username.to_lowercase();
+    match db::check_exists(&username, "username", "users") {
+        Ok(true) => (),
+        Ok(false) => return m_resp(StatusCode::BAD_REQUEST, "User not found."),
+        Err(err) => {
+            eprintln!("Error while checking if user exists: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    }
+    match db::grant_role(
+        &username,
+        body.role.as_str(),
+        &auth.username,
+        Utc::now().timestamp(),
+    ) {
+        Ok(true) => {
+            roles::audit(
+                &auth.username,
+                "grant_role",
+                &username,
+                Some(body.role.as_str()),
+            );
+            m_resp(
+                StatusCode::OK,
+                format!("Granted {} to {}", body.role.as_str(), username),
+            )
+        }
+        Ok(false) => m_resp(
+            StatusCode::CONFLICT,
+            format!("{} already has {}", username, body.role.as_str()),
+        ),
+        Err(err) => {
+            eprintln!("Error while granting role: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/admin/grant_role", post(handler))
+}
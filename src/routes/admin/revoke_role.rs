@@ -0,0 +1,76 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+
+use crate::{
+    routes::admin::grant_role::RoleBody,
+    utils::{
+        auth::JsonBody,
+        db,
+        res::m_resp,
+        roles::{self, Authorized, ManageRoles, Role},
+    },
+};
+
+pub async fn handler(
+    auth: Authorized<ManageRoles>,
+    JsonBody(body): JsonBody<RoleBody>,
+) -> impl IntoResponse {
+    let username = body.username.to_lowercase();
+    // Someone must be left who can grant roles again
+    if body.role == Role::SuperAdmin {
+        let remaining = db::get_user_roles(&username).and_then(|roles| {
+            let has_role = roles.iter().any(|role| role == Role::SuperAdmin.as_str());
+            db::count_role(Role::SuperAdmin.as_str()).map(|count| count - has_role as i64)
+        });
+        match remaining {
+            Ok(0) => {
+                return m_resp(
+                    StatusCode::BAD_REQUEST,
+                    "You can't revoke the last super admin.",
+                )
+            }
+            Ok(_) => (),
+            Err(err) => {
+                eprintln!("Error while counting super admins: {}", err);
+                return m_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Please contact support for help.",
+                );
+            }
+        }
+    }
+    match db::revoke_role(&username, body.role.as_str()) {
+        Ok(true) => {
+            roles::audit(
+                &auth.username,
+                "revoke_role",
+                &username,
+                Some(body.role.as_str()),
+            );
+            m_resp(
+                StatusCode::OK,
+                format!("Revoked {} from {}", body.role.as_str(), username),
+            )
+        }
+        Ok(false) => m_resp(
+            StatusCode::NOT_FOUND,
+            format!("{} doesn't have {}", username, body.role.as_str()),
+        ),
+        Err(err) => {
+            eprintln!("Error while revoking role: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/admin/revoke_role", post(handler))
+}
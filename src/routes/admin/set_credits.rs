@@ -4,66 +4,24 @@
     Find the LICENSE file in the root of this repository for more details.
 */
 
-use axum::{
-    body::{self, Body},
-    extract::Request,
-    http::StatusCode,
-    response::IntoResponse,
-    routing::post,
-    Router,
-};
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
 use serde::Deserialize;
-use serde_json::from_slice;
 
 use crate::utils::{
-    db::{self, is_admin},
+    auth::JsonBody,
+    db,
     res::m_resp,
-    validation,
+    roles::{self, Authorized, ManageCredits},
 };
 #[derive(Deserialize)]
-struct CreditsBody {
+pub struct CreditsBody {
     username: String,
     credits: i64,
 }
-pub async fn handler(req: Request<Body>) -> impl IntoResponse {
-    let (parts, body) = req.into_parts();
-    let (validated, username) = validation::validate_request(&parts.headers).await;
-    if !validated {
-        return m_resp(StatusCode::UNAUTHORIZED, "Invalid token");
-    }
-    if !match is_admin(username) {
-        Ok(b) => b,
-        Err(e) => {
-            eprintln!("Error occurred while checking for admin role: {}", e);
-            return m_resp(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Please contact support for help.",
-            );
-        }
-    } {
-        return m_resp(
-            StatusCode::FORBIDDEN,
-            "User doesn't have admin permissions.",
-        );
-    }
-    let body: CreditsBody =
-        match from_slice::<CreditsBody>(&match body::to_bytes(body, usize::MAX).await {
-            Ok(bytes) => bytes,
-            Err(_) => {
-                return m_resp(
-                    StatusCode::BAD_REQUEST,
-                    "Failed to parse bytes from request body",
-                )
-            }
-        }) {
-            Ok(info) => info,
-            Err(_) => {
-                return m_resp(
-                StatusCode::BAD_REQUEST,
-                "Failed to parse JSON from request body. Ensure the correct parameters are given.",
-            );
-            }
-        };
+pub async fn handler(
+    auth: Authorized<ManageCredits>,
+    JsonBody(body): JsonBody<CreditsBody>,
+) -> impl IntoResponse {
     if let Err(rusqlite::Error::QueryReturnedNoRows) = db::get_user_info(&body.username) {
         return m_resp(StatusCode::BAD_REQUEST, "User not found.");
     }
@@ -72,13 +30,19 @@ pub async fn handler(req: Request<Body>) -> impl IntoResponse {
     }
     match db::set_user_credits(&body.username, body.credits) {
         Ok(_) => {
+            roles::audit(
+                &auth.username,
+                "set_credits",
+                &body.username,
+                Some(&body.credits.to_string()),
+            );
             return m_resp(
                 StatusCode::OK,
                 format!(
                     "Successfully set {}'s credits to {}",
                     body.username, body.credits
                 ),
-            )
+            );
         }
         Err(e) => {
             eprintln!("Error while setting user credits: {}", e);
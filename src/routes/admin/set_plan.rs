@@ -0,0 +1,66 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::JsonBody,
+    bandwidth, db, hardening, idle,
+    res::m_resp,
+    roles::{self, Authorized, ManagePlans},
+};
+#[derive(Deserialize)]
+pub struct PlanBody {
+    username: String,
+    // None resets the user to the default plan settings
+    plan: Option<String>,
+}
+pub async fn handler(
+    auth: Authorized<ManagePlans>,
+    JsonBody(body): JsonBody<PlanBody>,
+) -> impl IntoResponse {
+    if let Err(rusqlite::Error::QueryReturnedNoRows) = db::get_user_info(&body.username) {
+        return m_resp(StatusCode::BAD_REQUEST, "User not found.");
+    }
+    if let Some(plan) = &body.plan {
+        if !hardening::plan_exists(plan)
+            && !bandwidth::plan_exists(plan)
+            && !idle::plan_exists(plan)
+        {
+            return m_resp(StatusCode::BAD_REQUEST, "Unknown plan.");
+        }
+    }
+    match db::set_user_plan(&body.username, body.plan.as_deref()) {
+        Ok(_) => {
+            roles::audit(
+                &auth.username,
+                "set_plan",
+                &body.username,
+                body.plan.as_deref(),
+            );
+            m_resp(
+                StatusCode::OK,
+                format!(
+                    "Successfully set {}'s plan to {}",
+                    body.username,
+                    body.plan.as_deref().unwrap_or("default")
+                ),
+            )
+        }
+        Err(e) => {
+            eprintln!("Error while setting user plan: {}", e);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/admin/set_plan", post(handler))
+}
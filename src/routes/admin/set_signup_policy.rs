@@ -0,0 +1,86 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::JsonBody,
+    res::m_resp,
+    roles::{self, Authorized, ManageSignups},
+    signup_policy,
+};
+
+// Fields that are left out keep their current value
+#[derive(Deserialize)]
+pub struct PolicyBody {
+    allowed_domains: Option<Vec<String>>,
+    denied_domains: Option<Vec<String>>,
+    invite_only: Option<bool>,
+}
+
+fn normalize(domains: Vec<String>) -> Option<Vec<String>> {
+    domains
+        .into_iter()
+        .map(|domain| {
+            let domain = domain.trim().to_lowercase();
+            let valid = domain.contains('.')
+                && domain
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-');
+            valid.then_some(domain)
+        })
+        .collect()
+}
+
+pub async fn handler(
+    auth: Authorized<ManageSignups>,
+    JsonBody(body): JsonBody<PolicyBody>,
+) -> impl IntoResponse {
+    let mut policy = match signup_policy::get() {
+        Ok(policy) => policy,
+        Err(err) => {
+            eprintln!("Error while getting signup policy: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    if let Some(domains) = body.allowed_domains {
+        match normalize(domains) {
+            Some(domains) => policy.allowed_domains = domains,
+            None => return m_resp(StatusCode::BAD_REQUEST, "Invalid allowed domain."),
+        }
+    }
+    if let Some(domains) = body.denied_domains {
+        match normalize(domains) {
+            Some(domains) => policy.denied_domains = domains,
+            None => return m_resp(StatusCode::BAD_REQUEST, "Invalid denied domain."),
+        }
+    }
+    if let Some(invite_only) = body.invite_only {
+        policy.invite_only = invite_only;
+    }
+    if let Err(err) = signup_policy::set(&policy) {
+        eprintln!("Error while setting signup policy: {}", err);
+        return m_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Please contact support for help.",
+        );
+    }
+    roles::audit(
+        &auth.username,
+        "set_signup_policy",
+        "signups",
+        serde_json::to_string(&policy).ok().as_deref(),
+    );
+    m_resp(StatusCode::OK, "Updated signup policy")
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/admin/set_signup_policy", post(handler))
+}
@@ -0,0 +1,61 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::net::IpAddr;
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+
+use crate::utils::{
+    auth::JsonBody,
+    db, lockout,
+    res::m_resp,
+    roles::{self, Authorized, UnlockAccounts},
+};
+
+#[derive(serde::Deserialize)]
+pub struct UnlockBody {
+    username: String,
+    // Also clears the counter of an address, e.g. one shared by an office
+    ip: Option<IpAddr>,
+}
+
+pub async fn handler(
+    auth: Authorized<UnlockAccounts>,
+    JsonBody(body): JsonBody<UnlockBody>,
+) -> impl IntoResponse {
+    let username = body.username.to_lowercase();
+    let mut keys = vec![lockout::user_key(&username)];
+    if let Some(ip) = &body.ip {
+        keys.push(lockout::ip_key(ip));
+    }
+    let mut cleared = false;
+    for key in &keys {
+        match db::clear_login_failures(key) {
+            Ok(deleted) => cleared |= deleted,
+            Err(err) => {
+                eprintln!("Error while unlocking {}: {}", key, err);
+                return m_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Please contact support for help.",
+                );
+            }
+        }
+    }
+    if !cleared {
+        return m_resp(StatusCode::NOT_FOUND, "No failed logins to clear.");
+    }
+    roles::audit(
+        &auth.username,
+        "unlock_account",
+        &username,
+        body.ip.map(|ip| ip.to_string()).as_deref(),
+    );
+    m_resp(StatusCode::OK, format!("Unlocked {}", username))
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/admin/unlock_account", post(handler))
+}
@@ -0,0 +1,73 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use chrono::{Duration, Utc};
+use rusqlite::Error;
+
+use crate::utils::{auth::JsonBody, db, mail, res::m_resp, validation};
+
+const RESET_EXPIRY_MINUTES: i64 = 30;
+
+#[derive(serde::Deserialize, Debug)]
+pub struct ForgotParams {
+    email: String,
+}
+
+pub async fn handler(JsonBody(payload): JsonBody<ForgotParams>) -> impl IntoResponse {
+    // The same response is given whether or not the account exists
+    let accepted = m_resp(
+        StatusCode::ACCEPTED,
+        "If an account exists for this email, a reset link has been sent.",
+    );
+    let email = payload.email.to_lowercase();
+    if !validation::validate_email(&email) {
+        return accepted;
+    }
+    let username = match db::get_user_info(&email) {
+        Ok((_, verified, username, _)) if verified != 0 => username,
+        Ok(_) | Err(Error::QueryReturnedNoRows) => return accepted,
+        Err(err) => {
+            eprintln!("An error occurred while getting user info: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    let token = validation::generate_token();
+    let expires = Utc::now() + Duration::minutes(RESET_EXPIRY_MINUTES);
+    if let Err(err) = db::insert_password_reset(
+        &username,
+        &validation::hash_token(&token),
+        expires.timestamp(),
+    ) {
+        eprintln!("An error occurred while inserting password reset: {}", err);
+        return m_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Please contact support for help.",
+        );
+    }
+    if let Err(err) = mail::send(
+        &email,
+        "Reset your Dockify password",
+        format!(
+            "A password reset was requested for your Dockify account.\n\nReset your password here, the link expires in {} minutes:\nhttps://dockify.xyz/reset?token={}\n\nIf you didn't request this, you can ignore this email.",
+            RESET_EXPIRY_MINUTES, token
+        ),
+    ) {
+        eprintln!("Error sending password reset email: {}", err);
+        return m_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Please contact support for help.",
+        );
+    }
+    accepted
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/password/forgot", post(handler))
+}
@@ -4,14 +4,21 @@
     Find the LICENSE file in the root of this repository for more details.
 */
 
-use axum::{http::StatusCode, response::IntoResponse, routing::post, Json, Router};
-use chrono::{Duration, Utc};
+use axum::{
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::post,
+    Router,
+};
 use rusqlite::Error;
 
 use crate::utils::{
-    db,
-    res::{jwt_resp, m_resp},
-    validation,
+    auth::{ClientIp, JsonBody},
+    db, lockout,
+    login_log::{self, Method},
+    mfa,
+    res::{m_resp, GenericResponse, Respond},
+    session, validation,
 };
 
 fn default_str() -> String {
@@ -28,7 +35,11 @@ pub struct LoginParams {
     password: String,
 }
 
-pub async fn handler(Json(payload): Json<LoginParams>) -> impl IntoResponse {
+pub async fn handler(
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    JsonBody(payload): JsonBody<LoginParams>,
+) -> impl IntoResponse {
     let id: &String = if payload.email.is_empty() {
         &payload.username.to_lowercase()
     } else {
@@ -42,6 +53,9 @@ pub async fn handler(Json(payload): Json<LoginParams>) -> impl IntoResponse {
             "Invalid username/email or password",
         );
     }
+    if let Err(err) = lockout::guard(None, &ip) {
+        return err;
+    }
     let (hash, username, _) = match db::get_user_info(&id) {
         Ok((hash, verified, username, email)) => {
             if verified == 0 {
@@ -55,10 +69,11 @@ pub async fn handler(Json(payload): Json<LoginParams>) -> impl IntoResponse {
         }
         Err(err) => match err {
             Error::QueryReturnedNoRows => {
+                lockout::record_failure(None, &ip);
                 return m_resp(
                     StatusCode::UNAUTHORIZED,
                     "Invalid username/email or password",
-                )
+                );
             }
             _ => {
                 eprintln!(
@@ -72,29 +87,69 @@ pub async fn handler(Json(payload): Json<LoginParams>) -> impl IntoResponse {
             }
         },
     };
+    if let Err(err) = lockout::guard(Some(&username), &ip) {
+        return err;
+    }
     match validation::verify_password(password, &hash) {
         Ok(_) => (),
         Err(_) => {
+            lockout::record_failure(Some(&username), &ip);
+            login_log::record(&username, &ip, &headers, Method::Password, false);
             return m_resp(
                 StatusCode::UNAUTHORIZED,
                 "Invalid username/email or password",
-            )
+            );
         }
     }
-    let jwt: String = match validation::generate_jwt(username, Utc::now() + Duration::hours(24)) {
-        Ok(jwt) => jwt,
+    match db::is_mfa_enabled(&username) {
+        Ok(false) => (),
+        Ok(true) => {
+            return match mfa::challenge_token(&username) {
+                Ok(challenge) => Respond::Generic(
+                    StatusCode::OK,
+                    GenericResponse::Challenge {
+                        mfa_required: true,
+                        challenge,
+                        expires_in: mfa::CHALLENGE_MINUTES * 60,
+                    },
+                ),
+                Err(err) => {
+                    eprintln!(
+                        "An error occurred while generating a 2FA challenge: {}",
+                        err
+                    );
+                    m_resp(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Please contact support for help.",
+                    )
+                }
+            }
+        }
+        Err(err) => {
+            eprintln!("An error occurred while checking 2FA in login: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    }
+    match session::create(&username) {
+        Ok(session) => {
+            lockout::record_success(&username);
+            login_log::record(&username, &ip, &headers, Method::Password, true);
+            session
+        }
         Err(err) => {
             eprintln!(
-                "An error occurred while generating a jwt for login: {}",
+                "An error occurred while creating a session for login: {}",
                 err
             );
-            return m_resp(
+            m_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Please contact support for help.",
-            );
+            )
         }
-    };
-    return jwt_resp(StatusCode::OK, jwt);
+    }
 }
 
 pub fn get_routes() -> Router {
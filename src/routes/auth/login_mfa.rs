@@ -0,0 +1,92 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::post,
+    Router,
+};
+
+use crate::utils::{
+    auth::{ClientIp, JsonBody},
+    lockout,
+    login_log::{self, Method},
+    mfa,
+    res::m_resp,
+    session,
+};
+
+fn default_str() -> String {
+    "".to_string()
+}
+
+// Second login step, takes the challenge from /api/login and a TOTP or recovery code
+#[derive(serde::Deserialize, Debug)]
+pub struct MfaParams {
+    challenge: String,
+    #[serde(default = "default_str")]
+    code: String,
+    #[serde(default = "default_str")]
+    recovery_code: String,
+}
+
+pub async fn handler(
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    JsonBody(payload): JsonBody<MfaParams>,
+) -> impl IntoResponse {
+    let Some(username) = mfa::verify_challenge(&payload.challenge).await else {
+        return m_resp(StatusCode::UNAUTHORIZED, "Invalid or expired challenge");
+    };
+    // Wrong codes count towards the same lockout as wrong passwords
+    if let Err(err) = lockout::guard(Some(&username), &ip) {
+        return err;
+    }
+    let valid = if !payload.code.is_empty() {
+        mfa::check_code(&username, &payload.code)
+    } else if !payload.recovery_code.is_empty() {
+        mfa::use_recovery_code(&username, &payload.recovery_code)
+    } else {
+        Ok(false)
+    };
+    match valid {
+        Ok(true) => (),
+        Ok(false) => {
+            lockout::record_failure(Some(&username), &ip);
+            login_log::record(&username, &ip, &headers, Method::Mfa, false);
+            return m_resp(StatusCode::UNAUTHORIZED, "Invalid code");
+        }
+        Err(err) => {
+            eprintln!("An error occurred while checking a 2FA code: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    }
+    match session::create(&username) {
+        Ok(session) => {
+            lockout::record_success(&username);
+            login_log::record(&username, &ip, &headers, Method::Mfa, true);
+            session
+        }
+        Err(err) => {
+            eprintln!(
+                "An error occurred while creating a session for login: {}",
+                err
+            );
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/login/2fa", post(handler))
+}
@@ -0,0 +1,34 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::post,
+    Router,
+};
+
+use crate::utils::{auth::User, db, res::m_resp, validation};
+
+pub async fn handler(User { username, .. }: User, headers: HeaderMap) -> impl IntoResponse {
+    let Some(session) = validation::request_session(&headers).await else {
+        return m_resp(StatusCode::UNAUTHORIZED, "Invalid token");
+    };
+    match db::revoke_session(&session, &username) {
+        Ok(_) => m_resp(StatusCode::OK, "Logged out"),
+        Err(err) => {
+            eprintln!("An error occurred while revoking a session: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/logout", post(handler))
+}
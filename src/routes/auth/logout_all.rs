@@ -0,0 +1,27 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+
+use crate::utils::{auth::User, db, res::m_resp};
+
+// Revokes every session of the user, including the one making the request
+pub async fn handler(User { username, .. }: User) -> impl IntoResponse {
+    match db::revoke_user_sessions(&username) {
+        Ok(count) => m_resp(StatusCode::OK, format!("Logged out of {} sessions", count)),
+        Err(err) => {
+            eprintln!("An error occurred while revoking user sessions: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/logout_all", post(handler))
+}
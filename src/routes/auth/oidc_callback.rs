@@ -0,0 +1,103 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{
+    extract::Query,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::ClientIp,
+    db,
+    login_log::{self, Method},
+    mfa, oidc,
+    res::m_resp,
+    session,
+};
+
+fn default_str() -> String {
+    "".to_string()
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CallbackParams {
+    #[serde(default = "default_str")]
+    code: String,
+    #[serde(default = "default_str")]
+    state: String,
+}
+
+// Tokens are passed to the frontend in the URL fragment so they never reach server logs
+fn redirect(base: &str, fragment: String) -> Response {
+    let mut headers = HeaderMap::new();
+    match format!("{}#{}", base, fragment).parse() {
+        Ok(location) => {
+            headers.insert(header::LOCATION, location);
+            (StatusCode::TEMPORARY_REDIRECT, headers).into_response()
+        }
+        Err(_) => m_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Please contact support for help.",
+        )
+        .into_response(),
+    }
+}
+
+pub async fn handler(
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    Query(params): Query<CallbackParams>,
+) -> Response {
+    let Some(config) = oidc::CONFIG.as_ref() else {
+        return m_resp(StatusCode::NOT_FOUND, "OIDC login is not configured").into_response();
+    };
+    let failed = || redirect(&config.frontend_redirect, "error=oidc_failed".to_string());
+    if params.code.is_empty() || params.state.is_empty() {
+        return failed();
+    }
+    let claims = match oidc::exchange(config, &params.code, &params.state).await {
+        Ok(claims) => claims,
+        Err(err) => {
+            eprintln!("An error occurred during OIDC login: {}", err);
+            return failed();
+        }
+    };
+    let username = match oidc::resolve_user(config, &claims) {
+        Ok(username) => username,
+        Err(err) => {
+            eprintln!("An error occurred while resolving OIDC user: {}", err);
+            return failed();
+        }
+    };
+    // Accounts with 2FA still have to finish the second step
+    let fragment = match db::is_mfa_enabled(&username) {
+        Ok(true) => mfa::challenge_token(&username)
+            .map(|challenge| format!("challenge={}", challenge))
+            .map_err(|err| err.to_string()),
+        Ok(false) => session::start(&username)
+            .map(|(token, refresh_token)| {
+                login_log::record(&username, &ip, &headers, Method::Oidc, true);
+                format!("token={}&refresh_token={}", token, refresh_token)
+            })
+            .map_err(|err| err.to_string()),
+        Err(err) => Err(err.to_string()),
+    };
+    match fragment {
+        Ok(fragment) => redirect(&config.frontend_redirect, fragment),
+        Err(err) => {
+            eprintln!("An error occurred while creating OIDC session: {}", err);
+            failed()
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/oidc/callback", get(handler))
+}
@@ -0,0 +1,48 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+
+use crate::utils::{oidc, res::m_resp};
+
+// Sends the browser to the configured OpenID Connect provider
+pub async fn handler() -> Response {
+    let Some(config) = oidc::CONFIG.as_ref() else {
+        return m_resp(StatusCode::NOT_FOUND, "OIDC login is not configured").into_response();
+    };
+    let url = match oidc::authorization_url(config).await {
+        Ok(url) => url,
+        Err(err) => {
+            eprintln!("An error occurred while starting OIDC login: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+            .into_response();
+        }
+    };
+    let mut headers = HeaderMap::new();
+    match url.parse() {
+        Ok(location) => {
+            headers.insert(header::LOCATION, location);
+            (StatusCode::TEMPORARY_REDIRECT, headers).into_response()
+        }
+        Err(_) => m_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Please contact support for help.",
+        )
+        .into_response(),
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/oidc/login", get(handler))
+}
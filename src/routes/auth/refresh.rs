@@ -0,0 +1,32 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+
+use crate::utils::{auth::JsonBody, res::m_resp, session};
+
+#[derive(serde::Deserialize, Debug)]
+pub struct RefreshParams {
+    refresh_token: String,
+}
+
+pub async fn handler(JsonBody(payload): JsonBody<RefreshParams>) -> impl IntoResponse {
+    match session::refresh(&payload.refresh_token) {
+        Ok(Some(session)) => session,
+        Ok(None) => m_resp(StatusCode::UNAUTHORIZED, "Invalid refresh token"),
+        Err(err) => {
+            eprintln!("An error occurred while refreshing a session: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/refresh", post(handler))
+}
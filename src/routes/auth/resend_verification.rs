@@ -0,0 +1,76 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use chrono::Utc;
+use rusqlite::Error;
+
+use crate::{
+    routes::auth::signup,
+    utils::{auth::JsonBody, db, res::m_resp, validation},
+};
+
+const RESEND_COOLDOWN_SECS: i64 = 60;
+const MAX_RESENDS_PER_HOUR: usize = 3;
+
+#[derive(serde::Deserialize, Debug)]
+pub struct ResendParams {
+    email: String,
+}
+
+pub async fn handler(JsonBody(payload): JsonBody<ResendParams>) -> impl IntoResponse {
+    // The same response is given whether or not the account exists
+    let accepted = m_resp(
+        StatusCode::ACCEPTED,
+        "If an unverified account exists for this email, a new link has been sent.",
+    );
+    let email = payload.email.to_lowercase();
+    if !validation::validate_email(&email) {
+        return accepted;
+    }
+    let username = match db::get_user_info(&email) {
+        Ok((_, 0, username, _)) => username,
+        Ok(_) | Err(Error::QueryReturnedNoRows) => return accepted,
+        Err(err) => {
+            eprintln!("An error occurred while getting user info: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    let now = Utc::now().timestamp();
+    let recent = match db::get_recent_codes(&username, now - 60 * 60) {
+        Ok(recent) => recent,
+        Err(err) => {
+            eprintln!(
+                "An error occurred while getting verification codes: {}",
+                err
+            );
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    let cooling_down = recent
+        .first()
+        .is_some_and(|created| now - created < RESEND_COOLDOWN_SECS);
+    if cooling_down || recent.len() >= MAX_RESENDS_PER_HOUR {
+        return m_resp(
+            StatusCode::TOO_MANY_REQUESTS,
+            "A verification email was sent recently, please try again later.",
+        );
+    }
+    match signup::send_verification(&username, &email) {
+        Ok(_) => accepted,
+        Err(status) => m_resp(status, "Please contact support for help."),
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/verify/resend", post(handler))
+}
@@ -0,0 +1,64 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use chrono::Utc;
+
+use crate::utils::{auth::JsonBody, db, lockout, res::m_resp, validation};
+
+#[derive(serde::Deserialize, Debug)]
+pub struct ResetParams {
+    token: String,
+    password: String,
+}
+
+pub async fn handler(JsonBody(payload): JsonBody<ResetParams>) -> impl IntoResponse {
+    if payload.password.is_empty() {
+        return m_resp(StatusCode::BAD_REQUEST, "Password can't be empty.");
+    }
+    let now = Utc::now().timestamp();
+    let username = match db::take_password_reset(&validation::hash_token(&payload.token), now) {
+        Ok(Some(username)) => username,
+        Ok(None) => return m_resp(StatusCode::BAD_REQUEST, "Invalid or expired reset token."),
+        Err(err) => {
+            eprintln!("An error occurred while checking reset token: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    let hash = match validation::hash_password(payload.password) {
+        Ok(hash) => hash,
+        Err(err) => {
+            eprintln!("An error occurred while hashing: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    match db::reset_password(&username, &hash) {
+        Ok(_) => {
+            // Proving ownership of the email lifts a lockout
+            if let Err(err) = db::clear_login_failures(&lockout::user_key(&username)) {
+                eprintln!("An error occurred while clearing failed logins: {}", err);
+            }
+            m_resp(StatusCode::OK, "Password has been reset.")
+        }
+        Err(err) => {
+            eprintln!("An error occurred while resetting password: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/password/reset", post(handler))
+}
@@ -5,20 +5,19 @@
 */
 
 use crate::utils::{
+    auth::JsonBody,
     db,
+    mail::MAILER,
+    signup_policy,
     validation::{self, validate_email},
 };
 use axum::http::StatusCode;
-use axum::Json;
 use axum::{response::IntoResponse, routing::post, Router};
 use base64::{engine::general_purpose, Engine as _};
 use chrono::Duration;
 use chrono::Utc;
-use dotenvy::var;
 use lettre::message::{header, Message};
-use lettre::transport::smtp::authentication::Credentials;
-use lettre::{SmtpTransport, Transport};
-use once_cell::sync::Lazy;
+use lettre::Transport;
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
@@ -26,25 +25,15 @@ pub struct AuthPayload {
     email: String,
     username: String,
     password: String,
+    // Required while signups are invite only
+    #[serde(default)]
+    invite_code: Option<String>,
 }
-static SMTP_USER: Lazy<String> =
-    Lazy::new(|| var("SMTP_USER").expect("Failed to retrieve SMTP_USER"));
-static SMTP_PASS: Lazy<String> =
-    Lazy::new(|| var("SMTP_PASS").expect("Failed to retrieve SMTP_PASS"));
-static CREDS: Lazy<Credentials> =
-    Lazy::new(|| Credentials::new(SMTP_USER.clone(), SMTP_PASS.clone()));
 
-static MAILER: Lazy<SmtpTransport> = Lazy::new(|| {
-    SmtpTransport::relay("mail.smtp2go.com")
-        .expect("Failed to connect to SMTP relay")
-        .credentials(CREDS.clone())
-        .build()
-});
-
-pub async fn handler(Json(payload): Json<AuthPayload>) -> impl IntoResponse {
+pub async fn handler(JsonBody(payload): JsonBody<AuthPayload>) -> impl IntoResponse {
     let username = payload.username;
     let email = payload.email;
-    if !&username.is_ascii() || !validate_email(&email) {
+    if !validation::validate_username(&username) || !validate_email(&email) {
         return StatusCode::BAD_REQUEST;
     }
     let checks = vec![
@@ -68,6 +57,10 @@ pub async fn handler(Json(payload): Json<AuthPayload>) -> impl IntoResponse {
             _ => {}
         }
     }
+    let invite = match signup_policy::admit(&email, payload.invite_code.as_deref()) {
+        Ok(invite) => invite,
+        Err(rejection) => return rejection.status(),
+    };
     let hash = match validation::hash_password(payload.password) {
         Ok(hash) => hash,
         Err(err) => {
@@ -75,32 +68,45 @@ pub async fn handler(Json(payload): Json<AuthPayload>) -> impl IntoResponse {
             return StatusCode::INTERNAL_SERVER_ERROR;
         }
     };
-    match db::insert_user(&email, &username, &hash, false) {
-        Ok(_) => (),
+    match db::insert_user_with_invite(&email, &username, &hash, invite.as_deref()) {
+        Ok(true) => (),
+        Ok(false) => return signup_policy::Rejection::InviteRequired.status(),
         Err(err) => {
             eprintln!("An error occurred while inserting user: {}", err);
+            return StatusCode::INTERNAL_SERVER_ERROR;
         }
     }
+    match send_verification(&username, &email) {
+        Ok(_) => StatusCode::ACCEPTED,
+        Err(status) => status,
+    }
+}
 
-    let verification_code =
-        match validation::generate_jwt(username.clone(), Utc::now() + Duration::hours(1)) {
-            Ok(jwt) => jwt,
-            Err(err) => {
-                eprintln!(
-                    "An error occurred while generating verification jwt: {}",
-                    err
-                );
-                return StatusCode::INTERNAL_SERVER_ERROR;
-            }
-        };
-    match db::insert_code(&username, &verification_code) {
+// Also used to resend the email when the previous link expired
+pub fn send_verification(username: &str, email: &str) -> Result<(), StatusCode> {
+    let expires = Utc::now() + Duration::hours(1);
+    let verification_code = match validation::generate_jwt(username.to_lowercase(), expires) {
+        Ok(jwt) => jwt,
+        Err(err) => {
+            eprintln!(
+                "An error occurred while generating verification jwt: {}",
+                err
+            );
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    match db::insert_code(
+        &username.to_lowercase(),
+        &verification_code,
+        expires.timestamp(),
+    ) {
         Ok(_) => (),
         Err(err) => {
             eprintln!(
                 "An error occurred while inserting verification jwt into db: {}",
                 err
             );
-            return StatusCode::INTERNAL_SERVER_ERROR;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
     }
     let email = match Message::builder()
@@ -108,14 +114,14 @@ pub async fn handler(Json(payload): Json<AuthPayload>) -> impl IntoResponse {
             Ok(addr) => addr,
             Err(e) => {
                 eprintln!("Error parsing 'from' address: {:?}", e);
-                return StatusCode::INTERNAL_SERVER_ERROR;
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
             }
         })
         .to(match email.parse() {
             Ok(addr) => addr,
             Err(e) => {
                 eprintln!("Error parsing 'to' address: {:?}", e);
-                return StatusCode::INTERNAL_SERVER_ERROR;
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
             }
         })
         .subject("Your Dockify Verification Email")
@@ -127,7 +133,7 @@ pub async fn handler(Json(payload): Json<AuthPayload>) -> impl IntoResponse {
         Ok(e) => e,
         Err(e) => {
             eprintln!("Error building email: {:?}", e);
-            return StatusCode::INTERNAL_SERVER_ERROR;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
     };
 
@@ -135,10 +141,10 @@ pub async fn handler(Json(payload): Json<AuthPayload>) -> impl IntoResponse {
         Ok(_) => (),
         Err(e) => {
             eprintln!("Error sending email: {:?}", e);
-            return StatusCode::INTERNAL_SERVER_ERROR;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
     }
-    return StatusCode::ACCEPTED;
+    Ok(())
 }
 
 pub fn get_routes() -> Router {
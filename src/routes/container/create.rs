@@ -5,29 +5,31 @@
 */
 
 use axum::{
-    body,
-    extract::Request,
     http::StatusCode,
     response::IntoResponse,
     routing::{post, Router},
 };
 use rand::distributions::{Alphanumeric, DistString};
 use serde::Deserialize;
-use serde_json::from_slice;
+use std::collections::HashMap;
 
 use crate::utils::{
-    container::{self, user_container_count, validate_container_resources},
+    auth::{JsonBody, User},
+    container::{
+        self, resolve_env, user_container_count, validate_container_resources, RuntimeOptions,
+    },
     db,
+    orgs::OrgRole,
     res::m_resp,
-    resources::ContainerResources,
-    validation,
+    resources::{self, default_disk, ContainerResources},
 };
 
 fn default_shares() -> i64 {
     512
 }
+pub const DEFAULT_IMAGE: &str = "dorowu/ubuntu-desktop-lxde-vnc";
 fn default_image() -> String {
-    "dorowu/ubuntu-desktop-lxde-vnc".to_string()
+    DEFAULT_IMAGE.to_string()
 }
 #[derive(Deserialize)]
 pub struct ContainerInfo {
@@ -38,14 +40,25 @@ pub struct ContainerInfo {
     pub cpu_cores: i64,
     #[serde(default = "default_shares")]
     pub cpu_shares: i64,
+    #[serde(default = "default_disk")]
+    pub disk: i64,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    // Environment variable name -> name of one of the user's stored secrets
+    #[serde(default)]
+    pub secrets: HashMap<String, String>,
+    #[serde(flatten)]
+    pub options: RuntimeOptions,
 }
 
-async fn handler(req: Request<axum::body::Body>) -> impl IntoResponse {
-    let (parts, body) = req.into_parts();
-    let (validated, username) = validation::validate_request(&parts.headers).await;
-    if !validated {
-        return m_resp(StatusCode::UNAUTHORIZED, "Invalid token");
-    }
+async fn handler(
+    user: User,
+    JsonBody(container_info): JsonBody<ContainerInfo>,
+) -> impl IntoResponse {
+    let username = match user.account(OrgRole::Developer) {
+        Ok(account) => account,
+        Err(err) => return err,
+    };
 
     let container_count = match user_container_count(&username) {
         Ok(count) => count,
@@ -53,8 +66,7 @@ async fn handler(req: Request<axum::body::Body>) -> impl IntoResponse {
             return err;
         }
     };
-    // TODO: Create user-specific container counts
-    if container_count > 1 {
+    if container_count >= container::MAX_CONTAINERS {
         return m_resp(
             StatusCode::FORBIDDEN,
             "User's plan has reached container limit, please delete existing containers.",
@@ -73,29 +85,22 @@ async fn handler(req: Request<axum::body::Body>) -> impl IntoResponse {
             }
         },
     };
-    let container_info: ContainerInfo =
-        match from_slice::<ContainerInfo>(&match body::to_bytes(body, usize::MAX).await {
-            Ok(bytes) => bytes,
-            Err(_) => {
-                return m_resp(
-                    StatusCode::BAD_REQUEST,
-                    "Failed to parse bytes from request body",
-                )
-            }
-        }) {
-            Ok(info) => info,
-            Err(_) => {
-                return m_resp(
-                StatusCode::BAD_REQUEST,
-                "Failed to parse JSON from request body. Ensure the correct parameters are given.",
-            );
-            }
-        };
+    if let Err(err) = container_info.options.validate() {
+        return m_resp(StatusCode::BAD_REQUEST, err);
+    }
+    if let Err(err) = resources::validate_disk(container_info.disk) {
+        return m_resp(StatusCode::BAD_REQUEST, err);
+    }
+    let env = match resolve_env(&username, &container_info.env, &container_info.secrets) {
+        Ok(env) => env,
+        Err(err) => return err,
+    };
     let resources = ContainerResources {
         cpu_shares: 512,
         memory: container_info.memory,
         memory_swap: container_info.memory_swap,
         cpu_cores: container_info.cpu_cores,
+        disk: container_info.disk,
     };
     let validate = match validate_container_resources(credits, &resources, Some(&username)) {
         Ok(b) => b,
@@ -121,6 +126,7 @@ async fn handler(req: Request<axum::body::Body>) -> impl IntoResponse {
     tokio::task::spawn(container::create_container(
         resources,
         container_info,
+        env,
         name.clone(),
         username,
     ));
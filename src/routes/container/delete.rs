@@ -4,48 +4,26 @@
     Find the LICENSE file in the root of this repository for more details.
 */
 
-use axum::{
-    body::{self, Body},
-    extract::Request,
-    http::StatusCode,
-    response::IntoResponse,
-    routing::post,
-    Router,
-};
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
 use bollard::Docker;
-use serde_json::from_slice;
 
 use crate::utils::{
+    auth::{JsonBody, User},
     container::{self, ContainerName},
-    db::get_user_containers,
+    db::{self, get_user_containers},
+    network,
+    orgs::OrgRole,
     res::m_resp,
-    validation,
 };
 
-pub async fn handler(req: Request<Body>) -> impl IntoResponse {
-    let (parts, body) = req.into_parts();
-    let (validated, username) = validation::validate_request(&parts.headers).await;
-    if !validated {
-        return m_resp(StatusCode::UNAUTHORIZED, "Invalid token");
-    }
-    let delete_params: ContainerName =
-        match from_slice::<ContainerName>(&match body::to_bytes(body, usize::MAX).await {
-            Ok(bytes) => bytes,
-            Err(_) => {
-                return m_resp(
-                    StatusCode::BAD_REQUEST,
-                    "Failed to parse bytes from request body",
-                )
-            }
-        }) {
-            Ok(info) => info,
-            Err(_) => {
-                return m_resp(
-                StatusCode::BAD_REQUEST,
-                "Failed to parse JSON from request body. Ensure the correct parameters are given.",
-            );
-            }
-        };
+pub async fn handler(
+    user: User,
+    JsonBody(delete_params): JsonBody<ContainerName>,
+) -> impl IntoResponse {
+    let username = match user.account(OrgRole::Developer) {
+        Ok(account) => account,
+        Err(err) => return err,
+    };
     let docker = match Docker::connect_with_local_defaults() {
         Ok(docker) => docker,
         Err(err) => {
@@ -87,6 +65,17 @@ pub async fn handler(req: Request<Body>) -> impl IntoResponse {
             );
         }
     }
+    if let Err(e) = db::delete_container(&delete_params.name, &username) {
+        eprintln!(
+            "An error occurred while removing a container from DB: {}",
+            e
+        );
+        return m_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Please contact support for help.",
+        );
+    }
+    network::remove_user_network_if_unused(&docker, &username).await;
     return m_resp(StatusCode::OK, "");
 }
 
@@ -0,0 +1,245 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::collections::HashMap;
+
+use axum::{
+    body::{self, Body},
+    extract::{
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        Path, Request,
+    },
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::any,
+    Router,
+};
+use futures_util::{SinkExt, StreamExt};
+use once_cell::sync::Lazy;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{self, client::IntoClientRequest, protocol::frame::coding::CloseCode},
+    MaybeTlsStream, WebSocketStream,
+};
+
+use crate::utils::{db, idle, orgs, res::m_resp};
+
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(reqwest::Client::new);
+// Only apply to a single connection, so they're never forwarded
+const HOP_BY_HOP: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+fn strip_hop_by_hop(headers: &mut HeaderMap) {
+    let listed: Vec<String> = headers
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|name| name.trim().to_lowercase())
+        .collect();
+    for name in listed.iter().map(String::as_str).chain(HOP_BY_HOP) {
+        headers.remove(name);
+    }
+}
+
+// Dockify's own credentials must not reach the tenant's container
+fn strip_credentials(headers: &mut HeaderMap) {
+    headers.remove(header::AUTHORIZATION);
+    headers.remove(header::COOKIE);
+    headers.remove(orgs::ORG_HEADER);
+}
+
+// Forwards requests to a container's published port, waking it if it was put to sleep.
+// WebSocket upgrades are relayed message by message.
+pub async fn handler(
+    Path(params): Path<HashMap<String, String>>,
+    ws: Option<WebSocketUpgrade>,
+    req: Request<Body>,
+) -> Response {
+    let name = params.get("name").cloned().unwrap_or_default();
+    let path = params.get("path").cloned().unwrap_or_default();
+    let (username, port, sleeping) = match db::get_container_route(&name) {
+        Ok(route) => route,
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            return m_resp(StatusCode::NOT_FOUND, "No container found with this name.")
+                .into_response()
+        }
+        Err(e) => {
+            eprintln!("An error occurred while getting container route: {}", e);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+            .into_response();
+        }
+    };
+    if port == 0 {
+        return m_resp(StatusCode::NOT_FOUND, "Container does not publish a port.").into_response();
+    }
+    if sleeping {
+        if let Err(e) = idle::wake(&name, &username, port).await {
+            eprintln!("An error occurred while waking container '{}': {}", name, e);
+            return m_resp(StatusCode::BAD_GATEWAY, "Failed to wake container.").into_response();
+        }
+    }
+
+    let (parts, body) = req.into_parts();
+    let mut target = format!("127.0.0.1:{}/{}", port, path);
+    if let Some(query) = parts.uri.query() {
+        target.push('?');
+        target.push_str(query);
+    }
+    if let Some(ws) = ws {
+        return proxy_websocket(ws, &name, &target, &parts.headers).await;
+    }
+    let body = match body::to_bytes(body, MAX_BODY_SIZE).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return m_resp(StatusCode::PAYLOAD_TOO_LARGE, "Request body is too large.")
+                .into_response()
+        }
+    };
+    let mut headers = parts.headers;
+    headers.remove(header::HOST);
+    strip_hop_by_hop(&mut headers);
+    strip_credentials(&mut headers);
+    let upstream = match CLIENT
+        .request(parts.method, format!("http://{}", target))
+        .headers(headers)
+        .body(body)
+        .send()
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!("An error occurred while proxying to '{}': {}", name, e);
+            return m_resp(StatusCode::BAD_GATEWAY, "Container is not responding.").into_response();
+        }
+    };
+    let status = upstream.status();
+    let mut headers = upstream.headers().clone();
+    strip_hop_by_hop(&mut headers);
+    match upstream.bytes().await {
+        Ok(bytes) => {
+            let mut response = Response::new(Body::from(bytes));
+            *response.status_mut() = status;
+            *response.headers_mut() = headers;
+            response
+        }
+        Err(e) => {
+            eprintln!(
+                "An error occurred while reading response from '{}': {}",
+                name, e
+            );
+            m_resp(StatusCode::BAD_GATEWAY, "Container is not responding.").into_response()
+        }
+    }
+}
+
+// Connects to the container first so a failed handshake is reported to the client
+async fn proxy_websocket(
+    ws: WebSocketUpgrade,
+    name: &str,
+    target: &str,
+    headers: &HeaderMap,
+) -> Response {
+    let mut request = match format!("ws://{}", target).into_client_request() {
+        Ok(request) => request,
+        Err(_) => return m_resp(StatusCode::BAD_REQUEST, "Invalid path.").into_response(),
+    };
+    // noVNC and similar clients only accept the subprotocol they asked for
+    if let Some(protocols) = headers.get(header::SEC_WEBSOCKET_PROTOCOL) {
+        request
+            .headers_mut()
+            .insert(header::SEC_WEBSOCKET_PROTOCOL, protocols.clone());
+    }
+    let (upstream, response) = match connect_async(request).await {
+        Ok(connection) => connection,
+        Err(e) => {
+            eprintln!(
+                "An error occurred while proxying a WebSocket to '{}': {}",
+                name, e
+            );
+            return m_resp(StatusCode::BAD_GATEWAY, "Container is not responding.").into_response();
+        }
+    };
+    let ws = match response
+        .headers()
+        .get(header::SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|value: &HeaderValue| value.to_str().ok())
+    {
+        Some(protocol) => ws.protocols([protocol.to_string()]),
+        None => ws,
+    };
+    ws.on_upgrade(|client| relay(client, upstream))
+}
+
+async fn relay(client: WebSocket, upstream: WebSocketStream<MaybeTlsStream<TcpStream>>) {
+    let (mut client_tx, mut client_rx) = client.split();
+    let (mut upstream_tx, mut upstream_rx) = upstream.split();
+    let to_upstream = async {
+        while let Some(Ok(message)) = client_rx.next().await {
+            let message = match message {
+                Message::Text(text) => tungstenite::Message::Text(text),
+                Message::Binary(data) => tungstenite::Message::Binary(data),
+                Message::Ping(data) => tungstenite::Message::Ping(data),
+                Message::Pong(data) => tungstenite::Message::Pong(data),
+                Message::Close(frame) => tungstenite::Message::Close(frame.map(|frame| {
+                    tungstenite::protocol::CloseFrame {
+                        code: CloseCode::from(frame.code),
+                        reason: frame.reason,
+                    }
+                })),
+            };
+            if upstream_tx.send(message).await.is_err() {
+                break;
+            }
+        }
+        let _ = upstream_tx.close().await;
+    };
+    let to_client = async {
+        while let Some(Ok(message)) = upstream_rx.next().await {
+            let message = match message {
+                tungstenite::Message::Text(text) => Message::Text(text),
+                tungstenite::Message::Binary(data) => Message::Binary(data),
+                tungstenite::Message::Ping(data) => Message::Ping(data),
+                tungstenite::Message::Pong(data) => Message::Pong(data),
+                tungstenite::Message::Close(frame) => {
+                    Message::Close(frame.map(|frame| CloseFrame {
+                        code: frame.code.into(),
+                        reason: frame.reason,
+                    }))
+                }
+                tungstenite::Message::Frame(_) => continue,
+            };
+            if client_tx.send(message).await.is_err() {
+                break;
+            }
+        }
+        let _ = client_tx.close().await;
+    };
+    // Either side closing ends the relay
+    tokio::select! {
+        _ = to_upstream => (),
+        _ = to_client => (),
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new()
+        .route("/proxy/:name", any(handler))
+        .route("/proxy/:name/*path", any(handler))
+}
@@ -0,0 +1,71 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+
+use crate::utils::{
+    auth::{JsonBody, User},
+    container::{self, ContainerName},
+    db,
+    orgs::OrgRole,
+    res::m_resp,
+};
+
+pub async fn handler(
+    user: User,
+    JsonBody(recreate_params): JsonBody<ContainerName>,
+) -> impl IntoResponse {
+    let username = match user.account(OrgRole::Developer) {
+        Ok(account) => account,
+        Err(err) => return err,
+    };
+    let containers = match db::get_user_containers(&username) {
+        Ok(c) => c,
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            return m_resp(StatusCode::NOT_FOUND, "No container found with this name.")
+        }
+        Err(e) => {
+            eprintln!("An error occurred while getting a user's containers: {}", e);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    if !container::container_exists(&containers, &recreate_params.name) {
+        return m_resp(StatusCode::NOT_FOUND, "No container found with this name.");
+    }
+    match db::get_container_stack(&recreate_params.name) {
+        Ok(None) => (),
+        Ok(Some(_)) => {
+            return m_resp(
+                StatusCode::BAD_REQUEST,
+                "Containers of a stack are managed through the stack.",
+            )
+        }
+        Err(e) => {
+            eprintln!("An error occurred while getting a container's stack: {}", e);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    }
+    match container::recreate_container(&recreate_params.name, &username).await {
+        Ok(_) => m_resp(StatusCode::OK, &recreate_params.name),
+        Err(e) => {
+            eprintln!("An error occurred while recreating user container: {}", e);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/recreate_container", post(handler))
+}
@@ -4,29 +4,24 @@
     Find the LICENSE file in the root of this repository for more details.
 */
 
-use axum::{
-    body::{self, Body},
-    extract::Request,
-    http::StatusCode,
-    response::IntoResponse,
-    routing::get,
-    Router,
-};
-use serde_json::from_slice;
+use axum::{http::StatusCode, response::IntoResponse, routing::get, Router};
 
 use crate::utils::{
+    auth::{JsonBody, User},
     container::{self, ContainerName},
     db,
+    orgs::OrgRole,
     res::m_resp,
-    validation,
 };
 
-pub async fn handler(req: Request<Body>) -> impl IntoResponse {
-    let (parts, body) = req.into_parts();
-    let (validated, username) = validation::validate_request(&parts.headers).await;
-    if !validated {
-        return m_resp(StatusCode::UNAUTHORIZED, "Invalid token");
-    }
+pub async fn handler(
+    user: User,
+    JsonBody(start_params): JsonBody<ContainerName>,
+) -> impl IntoResponse {
+    let username = match user.account(OrgRole::Developer) {
+        Ok(account) => account,
+        Err(err) => return err,
+    };
     let containers = match db::get_user_containers(&username) {
         Ok(c) => c,
         Err(e) => match e {
@@ -42,24 +37,6 @@ pub async fn handler(req: Request<Body>) -> impl IntoResponse {
             }
         },
     };
-    let start_params: ContainerName =
-        match from_slice::<ContainerName>(&match body::to_bytes(body, usize::MAX).await {
-            Ok(bytes) => bytes,
-            Err(_) => {
-                return m_resp(
-                    StatusCode::BAD_REQUEST,
-                    "Failed to parse bytes from request body",
-                )
-            }
-        }) {
-            Ok(info) => info,
-            Err(_) => {
-                return m_resp(
-                StatusCode::BAD_REQUEST,
-                "Failed to parse JSON from request body. Ensure the correct parameters are given.",
-            );
-            }
-        };
     if !container::container_exists(&containers, &start_params.name) || containers.is_empty() {
         return m_resp(StatusCode::NOT_FOUND, "No container found with this name.");
     }
@@ -0,0 +1,49 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{extract::Query, http::StatusCode, response::IntoResponse, routing::get, Router};
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::User,
+    db,
+    orgs::OrgRole,
+    res::{m_resp, GenericResponse, Respond},
+};
+
+const MAX_EVENTS: i64 = 200;
+
+fn default_limit() -> i64 {
+    50
+}
+#[derive(Deserialize)]
+pub struct EventQuery {
+    before: Option<i64>,
+    #[serde(default = "default_limit")]
+    limit: i64,
+}
+
+pub async fn handler(user: User, Query(query): Query<EventQuery>) -> impl IntoResponse {
+    let username = match user.account(OrgRole::Viewer) {
+        Ok(account) => account,
+        Err(err) => return err,
+    };
+    let limit = query.limit.clamp(1, MAX_EVENTS);
+    match db::get_container_events(&username, query.before, limit) {
+        Ok(events) => Respond::Generic(StatusCode::OK, GenericResponse::Events { events }),
+        Err(err) => {
+            eprintln!("An error occurred while getting user's events: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/events", get(handler))
+}
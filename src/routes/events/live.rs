@@ -0,0 +1,82 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::utils::{
+    events,
+    orgs::{self, OrgRole},
+    res::m_resp,
+    tokens, validation,
+};
+
+#[derive(Deserialize)]
+pub struct TokenQuery {
+    // Browsers can't set headers on WebSocket requests
+    token: Option<String>,
+    org: Option<String>,
+}
+
+pub async fn handler(
+    ws: WebSocketUpgrade,
+    Query(query): Query<TokenQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let (validated, username) = match query.token {
+        // Access tokens have to use the header so their scopes are checked
+        Some(token) if tokens::is_token(&token) => (false, "".to_string()),
+        Some(token) => validation::validate_token(token).await,
+        None => validation::validate_request(&headers).await,
+    };
+    if !validated {
+        return m_resp(StatusCode::UNAUTHORIZED, "Invalid token").into_response();
+    }
+    let org = query.org.as_deref().or(orgs::requested_org(&headers));
+    let username = match orgs::resolve_account(org, &username, OrgRole::Viewer) {
+        Ok(account) => account,
+        Err(err) => return err.into_response(),
+    };
+    ws.on_upgrade(move |socket| stream_events(socket, username))
+}
+
+async fn stream_events(mut socket: WebSocket, username: String) {
+    let mut events = events::subscribe();
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) if event.username == username => {
+                    let Ok(text) = serde_json::to_string(&event) else {
+                        continue;
+                    };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => (),
+                Err(RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                _ => (),
+            },
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/events/ws", get(handler))
+}
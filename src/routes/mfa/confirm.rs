@@ -0,0 +1,73 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::{JsonBody, User},
+    db, mfa,
+    res::{m_resp, GenericResponse, Respond},
+};
+
+#[derive(Deserialize)]
+pub struct CodeBody {
+    pub code: String,
+}
+
+pub async fn handler(
+    User { username, .. }: User,
+    JsonBody(body): JsonBody<CodeBody>,
+) -> impl IntoResponse {
+    match db::get_mfa(&username) {
+        Ok(Some(pending)) if !pending.enabled => (),
+        Ok(Some(_)) => {
+            return m_resp(
+                StatusCode::CONFLICT,
+                "Two-factor authentication is already enabled.",
+            )
+        }
+        Ok(None) => {
+            return m_resp(
+                StatusCode::BAD_REQUEST,
+                "Start enrollment before confirming a code.",
+            )
+        }
+        Err(err) => {
+            eprintln!("An error occurred while getting 2FA: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    }
+    let result = mfa::check_code(&username, &body.code).and_then(|valid| {
+        if !valid {
+            return Ok(None);
+        }
+        let codes = mfa::generate_recovery_codes(&username)?;
+        db::enable_mfa(&username)?;
+        Ok(Some(codes))
+    });
+    match result {
+        Ok(Some(recovery_codes)) => Respond::Generic(
+            StatusCode::OK,
+            GenericResponse::RecoveryCodes { recovery_codes },
+        ),
+        Ok(None) => m_resp(StatusCode::UNAUTHORIZED, "Invalid code"),
+        Err(err) => {
+            eprintln!("An error occurred while confirming 2FA: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/2fa/confirm", post(handler))
+}
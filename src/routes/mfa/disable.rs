@@ -0,0 +1,62 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+
+use crate::{
+    routes::mfa::confirm::CodeBody,
+    utils::{
+        auth::{JsonBody, User},
+        db, mfa,
+        res::m_resp,
+    },
+};
+
+// Accepts either a current code or one of the recovery codes
+pub async fn handler(
+    User { username, .. }: User,
+    JsonBody(body): JsonBody<CodeBody>,
+) -> impl IntoResponse {
+    match db::is_mfa_enabled(&username) {
+        Ok(true) => (),
+        Ok(false) => {
+            return m_resp(
+                StatusCode::BAD_REQUEST,
+                "Two-factor authentication is not enabled.",
+            )
+        }
+        Err(err) => {
+            eprintln!("An error occurred while checking 2FA: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    }
+    let result = mfa::check_code(&username, &body.code)
+        .and_then(|valid| Ok(valid || mfa::use_recovery_code(&username, &body.code)?))
+        .and_then(|valid| {
+            if valid {
+                db::delete_mfa(&username)?;
+            }
+            Ok(valid)
+        });
+    match result {
+        Ok(true) => m_resp(StatusCode::OK, "Two-factor authentication disabled"),
+        Ok(false) => m_resp(StatusCode::UNAUTHORIZED, "Invalid code"),
+        Err(err) => {
+            eprintln!("An error occurred while disabling 2FA: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/2fa/disable", post(handler))
+}
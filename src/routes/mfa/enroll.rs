@@ -0,0 +1,49 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+
+use crate::utils::{
+    auth::User,
+    db, mfa,
+    res::{m_resp, GenericResponse, Respond},
+};
+
+pub async fn handler(User { username, .. }: User) -> impl IntoResponse {
+    match db::is_mfa_enabled(&username) {
+        Ok(false) => (),
+        Ok(true) => {
+            return m_resp(
+                StatusCode::CONFLICT,
+                "Two-factor authentication is already enabled.",
+            )
+        }
+        Err(err) => {
+            eprintln!("An error occurred while checking 2FA: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    }
+    match mfa::enroll(&username) {
+        Ok((uri, secret)) => Respond::Generic(
+            StatusCode::OK,
+            GenericResponse::TotpEnrollment { uri, secret },
+        ),
+        Err(err) => {
+            eprintln!("An error occurred while enrolling 2FA: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/2fa/enroll", post(handler))
+}
@@ -0,0 +1,46 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::{JsonBody, User},
+    db,
+    res::m_resp,
+    webhook,
+};
+
+#[derive(Deserialize)]
+pub struct NotificationBody {
+    email: bool,
+    webhook: Option<String>,
+}
+
+pub async fn handler(
+    User { username, .. }: User,
+    JsonBody(body): JsonBody<NotificationBody>,
+) -> impl IntoResponse {
+    if let Some(webhook) = &body.webhook {
+        if let Err(err) = webhook::resolve(webhook).await {
+            return m_resp(StatusCode::BAD_REQUEST, err);
+        }
+    }
+    match db::set_notification_settings(&username, body.email, body.webhook.as_deref()) {
+        Ok(_) => m_resp(StatusCode::OK, ""),
+        Err(err) => {
+            eprintln!("Error while setting notification settings: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/set_notifications", post(handler))
+}
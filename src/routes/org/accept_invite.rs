@@ -0,0 +1,65 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::{JsonBody, User},
+    db,
+    res::m_resp,
+    validation,
+};
+
+#[derive(Deserialize)]
+pub struct AcceptBody {
+    token: String,
+}
+
+pub async fn handler(
+    User { username, .. }: User,
+    JsonBody(body): JsonBody<AcceptBody>,
+) -> impl IntoResponse {
+    let email = match db::get_user_info(&username) {
+        Ok((_, _, _, email)) => email,
+        Err(err) => {
+            eprintln!("Error while getting user info: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    let now = Utc::now().timestamp();
+    let (slug, role) = match db::take_org_invite(&validation::hash_token(&body.token), &email, now)
+    {
+        Ok(Some(invite)) => invite,
+        Ok(None) => return m_resp(StatusCode::BAD_REQUEST, "Invalid or expired invite."),
+        Err(err) => {
+            eprintln!("Error while taking org invite: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    match db::add_org_member(&slug, &username, &role, now) {
+        Ok(true) => m_resp(StatusCode::OK, format!("Joined {} as {}", slug, role)),
+        Ok(false) => m_resp(StatusCode::CONFLICT, "You're already a member of this org."),
+        Err(err) => {
+            eprintln!("Error while adding org member: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/org/accept_invite", post(handler))
+}
@@ -0,0 +1,61 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::{JsonBody, User},
+    db, orgs,
+    res::m_resp,
+};
+
+const MAX_NAME_LEN: usize = 64;
+
+#[derive(Deserialize)]
+pub struct OrgBody {
+    slug: String,
+    name: String,
+}
+
+pub async fn handler(
+    User { username, .. }: User,
+    JsonBody(body): JsonBody<OrgBody>,
+) -> impl IntoResponse {
+    let slug = body.slug.to_lowercase();
+    if !orgs::validate_slug(&slug) {
+        return m_resp(
+            StatusCode::BAD_REQUEST,
+            "Org slugs must be 3-32 lowercase letters, digits or dashes.",
+        );
+    }
+    let name = body.name.trim();
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return m_resp(StatusCode::BAD_REQUEST, "Please provide a valid org name.");
+    }
+    match db::insert_org(
+        &slug,
+        &orgs::account(&slug),
+        name,
+        &username,
+        Utc::now().timestamp(),
+    ) {
+        Ok(true) => m_resp(StatusCode::CREATED, slug),
+        Ok(false) => m_resp(StatusCode::CONFLICT, "This org slug is taken."),
+        Err(err) => {
+            eprintln!("Error while creating org: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/org/create", post(handler))
+}
@@ -0,0 +1,61 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::{JsonBody, User},
+    db,
+    orgs::{self, OrgRole},
+    res::m_resp,
+};
+
+#[derive(Deserialize)]
+pub struct OrgName {
+    pub org: String,
+}
+
+pub async fn handler(
+    User { username, .. }: User,
+    JsonBody(body): JsonBody<OrgName>,
+) -> impl IntoResponse {
+    let slug = body.org.to_lowercase();
+    if let Err(err) = orgs::require_role(&slug, &username, OrgRole::Owner) {
+        return err;
+    }
+    let account = orgs::account(&slug);
+    match db::count_containers_by_username(&account) {
+        Ok(0) => (),
+        Ok(_) => {
+            return m_resp(
+                StatusCode::CONFLICT,
+                "Delete the org's containers before deleting the org.",
+            )
+        }
+        Err(err) => {
+            eprintln!("Error while counting org containers: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    }
+    match db::delete_org(&slug, &account, &username) {
+        Ok(_) => m_resp(StatusCode::OK, ""),
+        Err(err) => {
+            eprintln!("Error while deleting org: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/org/delete", post(handler))
+}
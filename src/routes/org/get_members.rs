@@ -0,0 +1,41 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{extract::Query, http::StatusCode, response::IntoResponse, routing::get, Router};
+
+use crate::{
+    routes::org::delete::OrgName,
+    utils::{
+        auth::User,
+        db,
+        orgs::{self, OrgRole},
+        res::{m_resp, GenericResponse, Respond},
+    },
+};
+
+pub async fn handler(
+    User { username, .. }: User,
+    Query(query): Query<OrgName>,
+) -> impl IntoResponse {
+    let slug = query.org.to_lowercase();
+    if let Err(err) = orgs::require_role(&slug, &username, OrgRole::Viewer) {
+        return err;
+    }
+    match db::get_org_members(&slug) {
+        Ok(members) => Respond::Generic(StatusCode::OK, GenericResponse::OrgMembers { members }),
+        Err(err) => {
+            eprintln!("An error occurred while getting org members: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/org/members", get(handler))
+}
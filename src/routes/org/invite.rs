@@ -0,0 +1,90 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::{JsonBody, User},
+    db, mail,
+    orgs::{self, OrgRole},
+    res::m_resp,
+    validation,
+};
+
+const INVITE_EXPIRY_DAYS: i64 = 7;
+
+#[derive(Deserialize)]
+pub struct InviteBody {
+    org: String,
+    email: String,
+    role: OrgRole,
+}
+
+pub async fn handler(
+    User { username, .. }: User,
+    JsonBody(body): JsonBody<InviteBody>,
+) -> impl IntoResponse {
+    let slug = body.org.to_lowercase();
+    let role = match orgs::require_role(&slug, &username, OrgRole::Admin) {
+        Ok(role) => role,
+        Err(err) => return err,
+    };
+    // Admins can't hand out more access than they have
+    if body.role > role {
+        return m_resp(
+            StatusCode::FORBIDDEN,
+            "Only owners can invite other owners.",
+        );
+    }
+    let email = body.email.to_lowercase();
+    if !validation::validate_email(&email) {
+        return m_resp(StatusCode::BAD_REQUEST, "Please provide a valid email.");
+    }
+    let token = validation::generate_token();
+    let expires = Utc::now() + Duration::days(INVITE_EXPIRY_DAYS);
+    if let Err(err) = db::insert_org_invite(
+        &validation::hash_token(&token),
+        &slug,
+        &email,
+        body.role.as_str(),
+        &username,
+        expires.timestamp(),
+    ) {
+        eprintln!("Error while inserting org invite: {}", err);
+        return m_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Please contact support for help.",
+        );
+    }
+    if let Err(err) = mail::send(
+        &email,
+        &format!("You've been invited to {} on Dockify", slug),
+        format!(
+            "{} invited you to join the {} org on Dockify as {}.\n\nAccept the invite here, the link expires in {} days:\nhttps://dockify.xyz/invite?token={}\n\nYou'll need a Dockify account using this email address.",
+            username,
+            slug,
+            body.role.as_str(),
+            INVITE_EXPIRY_DAYS,
+            token
+        ),
+    ) {
+        eprintln!("Error sending org invite email: {}", err);
+        return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+    }
+    m_resp(
+        StatusCode::ACCEPTED,
+        format!("Invited {} to {}", email, slug),
+    )
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/org/invite", post(handler))
+}
@@ -0,0 +1,74 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::{JsonBody, User},
+    db,
+    orgs::{self, OrgRole},
+    res::m_resp,
+};
+
+#[derive(Deserialize)]
+pub struct MemberBody {
+    org: String,
+    username: String,
+}
+
+// Members can remove themselves, admins and owners can remove members below them
+pub async fn handler(
+    User { username, .. }: User,
+    JsonBody(body): JsonBody<MemberBody>,
+) -> impl IntoResponse {
+    let slug = body.org.to_lowercase();
+    let member = body.username.to_lowercase();
+    let role = match orgs::require_role(&slug, &username, OrgRole::Viewer) {
+        Ok(role) => role,
+        Err(err) => return err,
+    };
+    let member_role = match orgs::member_role(&slug, &member) {
+        Ok(Some(role)) => role,
+        Ok(None) => return m_resp(StatusCode::NOT_FOUND, "User isn't a member of this org."),
+        Err(err) => return err,
+    };
+    if member != username
+        && (role < OrgRole::Admin || (member_role >= role && role != OrgRole::Owner))
+    {
+        return m_resp(
+            StatusCode::FORBIDDEN,
+            "You can't remove this member from the org.",
+        );
+    }
+    if member_role == OrgRole::Owner {
+        match db::count_org_owners(&slug) {
+            Ok(owners) if owners > 1 => (),
+            Ok(_) => return m_resp(StatusCode::BAD_REQUEST, "An org needs at least one owner."),
+            Err(err) => {
+                eprintln!("Error while counting org owners: {}", err);
+                return m_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Please contact support for help.",
+                );
+            }
+        }
+    }
+    match db::remove_org_member(&slug, &member) {
+        Ok(_) => m_resp(StatusCode::OK, ""),
+        Err(err) => {
+            eprintln!("Error while removing org member: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/org/remove_member", post(handler))
+}
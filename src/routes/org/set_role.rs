@@ -0,0 +1,68 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::{JsonBody, User},
+    db,
+    orgs::{self, OrgRole},
+    res::m_resp,
+};
+
+#[derive(Deserialize)]
+pub struct SetRoleBody {
+    org: String,
+    username: String,
+    role: OrgRole,
+}
+
+pub async fn handler(
+    User { username, .. }: User,
+    JsonBody(body): JsonBody<SetRoleBody>,
+) -> impl IntoResponse {
+    let slug = body.org.to_lowercase();
+    let member = body.username.to_lowercase();
+    if let Err(err) = orgs::require_role(&slug, &username, OrgRole::Owner) {
+        return err;
+    }
+    let current = match orgs::member_role(&slug, &member) {
+        Ok(Some(role)) => role,
+        Ok(None) => return m_resp(StatusCode::NOT_FOUND, "User isn't a member of this org."),
+        Err(err) => return err,
+    };
+    if current == OrgRole::Owner && body.role != OrgRole::Owner {
+        match db::count_org_owners(&slug) {
+            Ok(owners) if owners > 1 => (),
+            Ok(_) => return m_resp(StatusCode::BAD_REQUEST, "An org needs at least one owner."),
+            Err(err) => {
+                eprintln!("Error while counting org owners: {}", err);
+                return m_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Please contact support for help.",
+                );
+            }
+        }
+    }
+    match db::set_org_member_role(&slug, &member, body.role.as_str()) {
+        Ok(_) => m_resp(
+            StatusCode::OK,
+            format!("{} is now {} of {}", member, body.role.as_str(), slug),
+        ),
+        Err(err) => {
+            eprintln!("Error while setting org member role: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/org/set_role", post(handler))
+}
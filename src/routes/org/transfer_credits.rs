@@ -0,0 +1,56 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::{JsonBody, User},
+    db,
+    orgs::{self, OrgRole},
+    res::m_resp,
+};
+
+#[derive(Deserialize)]
+pub struct TransferBody {
+    org: String,
+    credits: i64,
+}
+
+// Moves credits from the user's own balance to the org's
+pub async fn handler(
+    User { username, .. }: User,
+    JsonBody(body): JsonBody<TransferBody>,
+) -> impl IntoResponse {
+    let slug = body.org.to_lowercase();
+    if let Err(err) = orgs::require_role(&slug, &username, OrgRole::Admin) {
+        return err;
+    }
+    if body.credits <= 0 {
+        return m_resp(StatusCode::BAD_REQUEST, "Please set a valid number.");
+    }
+    match db::transfer_credits(&username, &orgs::account(&slug), body.credits) {
+        Ok(true) => m_resp(
+            StatusCode::OK,
+            format!("Transferred {} credits to {}", body.credits, slug),
+        ),
+        Ok(false) => m_resp(
+            StatusCode::PAYMENT_REQUIRED,
+            "Not enough credits in user's account.",
+        ),
+        Err(err) => {
+            eprintln!("Error while transferring credits: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/org/transfer_credits", post(handler))
+}
@@ -0,0 +1,94 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::{JsonBody, User},
+    container, db,
+    orgs::OrgRole,
+    res::m_resp,
+    schedule::{self, Action},
+};
+
+const MAX_SCHEDULES: usize = 16;
+
+#[derive(Deserialize)]
+pub struct ScheduleBody {
+    name: String,
+    action: Action,
+    cron: String,
+}
+
+pub async fn handler(user: User, JsonBody(body): JsonBody<ScheduleBody>) -> impl IntoResponse {
+    let username = match user.account(OrgRole::Developer) {
+        Ok(account) => account,
+        Err(err) => return err,
+    };
+    let cron = match schedule::parse_cron(&body.cron) {
+        Ok(cron) => cron,
+        Err(err) => {
+            return m_resp(
+                StatusCode::BAD_REQUEST,
+                format!("Invalid cron expression: {}", err),
+            )
+        }
+    };
+    let containers = match db::get_user_containers(&username) {
+        Ok(c) => c,
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            return m_resp(StatusCode::NOT_FOUND, "No container found with this name.")
+        }
+        Err(e) => {
+            eprintln!("An error occurred while getting a user's containers: {}", e);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    if !container::container_exists(&containers, &body.name) {
+        return m_resp(StatusCode::NOT_FOUND, "No container found with this name.");
+    }
+    match db::get_user_schedules(&username) {
+        Ok(schedules) if schedules.len() >= MAX_SCHEDULES => {
+            return m_resp(
+                StatusCode::FORBIDDEN,
+                "User has reached the schedule limit, please delete existing schedules.",
+            )
+        }
+        Ok(_) => (),
+        Err(e) => {
+            eprintln!("An error occurred while getting a user's schedules: {}", e);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    }
+    match db::insert_schedule(
+        &username,
+        &body.name,
+        body.action.as_str(),
+        body.cron.trim(),
+        schedule::next_run(&cron, Utc::now()),
+    ) {
+        Ok(id) => m_resp(StatusCode::OK, id.to_string()),
+        Err(e) => {
+            eprintln!("An error occurred while inserting a schedule: {}", e);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/create_schedule", post(handler))
+}
@@ -0,0 +1,42 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::{JsonBody, User},
+    db,
+    orgs::OrgRole,
+    res::m_resp,
+};
+
+#[derive(Deserialize)]
+pub struct SecretName {
+    name: String,
+}
+
+pub async fn handler(user: User, JsonBody(body): JsonBody<SecretName>) -> impl IntoResponse {
+    let username = match user.account(OrgRole::Admin) {
+        Ok(account) => account,
+        Err(err) => return err,
+    };
+    match db::delete_secret(&username, &body.name) {
+        Ok(0) => m_resp(StatusCode::NOT_FOUND, "No secret found with this name."),
+        Ok(_) => m_resp(StatusCode::OK, ""),
+        Err(err) => {
+            eprintln!("Error while deleting secret: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/delete_secret", post(handler))
+}
@@ -0,0 +1,81 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::{JsonBody, User},
+    db,
+    orgs::OrgRole,
+    res::m_resp,
+    secrets,
+};
+
+const MAX_SECRETS: usize = 64;
+const MAX_SECRET_LEN: usize = 32 * 1024;
+
+#[derive(Deserialize)]
+pub struct SecretBody {
+    name: String,
+    value: String,
+}
+
+pub async fn handler(user: User, JsonBody(body): JsonBody<SecretBody>) -> impl IntoResponse {
+    let username = match user.account(OrgRole::Admin) {
+        Ok(account) => account,
+        Err(err) => return err,
+    };
+    if !secrets::validate_name(&body.name) {
+        return m_resp(
+            StatusCode::BAD_REQUEST,
+            "Secret names may only contain letters, digits and underscores.",
+        );
+    }
+    if body.value.len() > MAX_SECRET_LEN {
+        return m_resp(StatusCode::BAD_REQUEST, "Secret value is too large.");
+    }
+    let names = match db::get_secret_names(&username) {
+        Ok(names) => names,
+        Err(err) => {
+            eprintln!("Error while getting user secrets: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    if names.len() >= MAX_SECRETS && !names.contains(&body.name) {
+        return m_resp(
+            StatusCode::FORBIDDEN,
+            "User has reached the secret limit, please delete existing secrets.",
+        );
+    }
+    let (nonce, ciphertext) = match secrets::encrypt(&body.value) {
+        Ok(encrypted) => encrypted,
+        Err(err) => {
+            eprintln!("Error while encrypting secret: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    match db::set_secret(&username, &body.name, &nonce, &ciphertext) {
+        Ok(_) => m_resp(StatusCode::OK, &body.name),
+        Err(err) => {
+            eprintln!("Error while storing secret: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/set_secret", post(handler))
+}
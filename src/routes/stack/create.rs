@@ -0,0 +1,113 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{
+    body::{self, Body},
+    extract::Request,
+    http::StatusCode,
+    response::IntoResponse,
+    routing::post,
+    Router,
+};
+use rand::distributions::{Alphanumeric, DistString};
+
+use crate::utils::{
+    auth::{User, MAX_JSON_BODY},
+    container::{self, user_container_count, validate_container_resources},
+    db,
+    orgs::OrgRole,
+    res::m_resp,
+    stack::{self, StackSpec},
+};
+
+pub async fn handler(user: User, req: Request<Body>) -> impl IntoResponse {
+    let username = match user.account(OrgRole::Developer) {
+        Ok(account) => account,
+        Err(err) => return err,
+    };
+    let spec = match StackSpec::parse(
+        &match body::to_bytes(req.into_body(), MAX_JSON_BODY).await {
+            Ok(bytes) => bytes,
+            Err(_) => return m_resp(StatusCode::PAYLOAD_TOO_LARGE, "Request body is too large."),
+        },
+    ) {
+        Ok(spec) => spec,
+        Err(err) => {
+            return m_resp(
+                StatusCode::BAD_REQUEST,
+                format!("Failed to parse stack spec: {}", err),
+            )
+        }
+    };
+    let order = match spec.validate() {
+        Ok(order) => order,
+        Err(err) => return m_resp(StatusCode::BAD_REQUEST, err),
+    };
+
+    let container_count = match user_container_count(&username) {
+        Ok(count) => count,
+        Err(err) => {
+            return err;
+        }
+    };
+    if container_count + order.len() as i32 > container::MAX_CONTAINERS {
+        return m_resp(
+            StatusCode::FORBIDDEN,
+            "User's plan has reached container limit, please delete existing containers.",
+        );
+    }
+    let credits = match db::get_user_credits(&username) {
+        Ok(credits) => credits,
+        Err(err) => match err {
+            rusqlite::Error::QueryReturnedNoRows => 0,
+            _ => {
+                eprintln!("Error while getting user credits: {}", err);
+                return m_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Please contact support for help.",
+                );
+            }
+        },
+    };
+    let envs = match stack::resolve_stack_env(&username, &spec) {
+        Ok(envs) => envs,
+        Err(err) => return err,
+    };
+    let validate =
+        match validate_container_resources(credits, &spec.total_resources(), Some(&username)) {
+            Ok(b) => b,
+            Err(err) => {
+                eprintln!(
+                    "Error occurred while validating user's stack resources: {}",
+                    err
+                );
+                return m_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Please contact support for help.",
+                );
+            }
+        };
+    if !validate {
+        return m_resp(
+            StatusCode::PAYMENT_REQUIRED,
+            "Not enough credits in user's account.",
+        );
+    }
+
+    let name: String = Alphanumeric.sample_string(&mut rand::thread_rng(), 16);
+    tokio::task::spawn(stack::create_stack(
+        spec,
+        order,
+        envs,
+        name.clone(),
+        username,
+    ));
+    m_resp(StatusCode::ACCEPTED, &name)
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/create_stack", post(handler))
+}
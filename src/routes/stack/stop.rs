@@ -0,0 +1,50 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+
+use crate::utils::{
+    auth::{JsonBody, User},
+    container::{self, ContainerName},
+    db,
+    orgs::OrgRole,
+    res::m_resp,
+};
+
+pub async fn handler(user: User, JsonBody(params): JsonBody<ContainerName>) -> impl IntoResponse {
+    let username = match user.account(OrgRole::Developer) {
+        Ok(account) => account,
+        Err(err) => return err,
+    };
+    let containers = match db::get_stack_containers(&params.name, &username) {
+        Ok(c) => c,
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            return m_resp(StatusCode::NOT_FOUND, "No stack found with this name.")
+        }
+        Err(e) => {
+            eprintln!("An error occurred while getting a user's stack: {}", e);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    };
+    // Stop dependents before the services they depend on
+    for name in containers.iter().rev() {
+        if let Err(e) = container::stop_container(name).await {
+            eprintln!("An error occurred while stopping stack container: {}", e);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    }
+    m_resp(StatusCode::OK, &params.name)
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/stop_stack", post(handler))
+}
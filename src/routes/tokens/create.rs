@@ -0,0 +1,96 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+
+use crate::utils::{
+    auth::{JsonBody, User},
+    db,
+    res::{m_resp, GenericResponse, Respond},
+    tokens::{self, Scope},
+    validation,
+};
+
+const MAX_TOKENS: usize = 20;
+const MAX_NAME_LEN: usize = 64;
+
+#[derive(Deserialize)]
+pub struct TokenBody {
+    name: String,
+    scopes: Vec<Scope>,
+    // Tokens without an expiry stay valid until revoked
+    expires_in_days: Option<i64>,
+}
+
+pub async fn handler(
+    User { username, .. }: User,
+    JsonBody(body): JsonBody<TokenBody>,
+) -> impl IntoResponse {
+    if body.name.trim().is_empty() || body.name.len() > MAX_NAME_LEN {
+        return m_resp(StatusCode::BAD_REQUEST, "Invalid token name.");
+    }
+    if body.scopes.is_empty() {
+        return m_resp(StatusCode::BAD_REQUEST, "Tokens need at least one scope.");
+    }
+    if body
+        .expires_in_days
+        .is_some_and(|days| !(1..=365).contains(&days))
+    {
+        return m_resp(
+            StatusCode::BAD_REQUEST,
+            "expires_in_days must be between 1 and 365.",
+        );
+    }
+    match db::get_api_tokens(&username) {
+        Ok(existing) if existing.len() >= MAX_TOKENS => {
+            return m_resp(
+                StatusCode::FORBIDDEN,
+                "User has reached the token limit, please delete existing tokens.",
+            )
+        }
+        Ok(_) => (),
+        Err(err) => {
+            eprintln!("Error while getting user tokens: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            );
+        }
+    }
+    let mut scopes: Vec<String> = Vec::new();
+    for scope in body.scopes {
+        if !scopes.iter().any(|s| s == scope.as_str()) {
+            scopes.push(scope.as_str().to_string());
+        }
+    }
+    let expires = body
+        .expires_in_days
+        .map(|days| (Utc::now() + Duration::days(days)).timestamp());
+    let token = tokens::generate();
+    match db::insert_api_token(
+        &username,
+        body.name.trim(),
+        &validation::hash_token(&token),
+        &scopes,
+        expires,
+    ) {
+        // The token is only ever shown here
+        Ok(id) => Respond::Generic(StatusCode::OK, GenericResponse::ApiToken { id, token }),
+        Err(err) => {
+            eprintln!("Error while inserting token: {}", err);
+            m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+        }
+    }
+}
+
+pub fn get_routes() -> Router {
+    Router::new().route("/api/create_token", post(handler))
+}
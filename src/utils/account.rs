@@ -0,0 +1,66 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::{error::Error, net::IpAddr};
+
+use axum::http::StatusCode;
+use bollard::Docker;
+
+use crate::utils::{
+    container, db, lockout, network,
+    res::{m_resp, Respond},
+    stack, validation,
+};
+
+// Sensitive account changes ask for the password again. Wrong passwords count
+// towards the same lockout as failed logins.
+pub fn confirm_password(username: &str, password: &str, ip: &IpAddr) -> Result<(), Respond> {
+    lockout::guard(Some(username), ip)?;
+    let hash = match db::get_user_info(username) {
+        Ok((hash, _, _, _)) => hash,
+        Err(err) => {
+            eprintln!("An error occurred while getting user info: {}", err);
+            return Err(m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            ));
+        }
+    };
+    if password.is_empty() || validation::verify_password(password, &hash).is_err() {
+        lockout::record_failure(Some(username), ip);
+        return Err(m_resp(
+            StatusCode::UNAUTHORIZED,
+            "Current password is incorrect.",
+        ));
+    }
+    Ok(())
+}
+
+// Removes the user's stacks, containers and network from Docker, which frees
+// their ports, then purges their rows from the database
+pub async fn delete(username: &str, email: &str) -> Result<(), Box<dyn Error>> {
+    let stacks = db::get_user_stacks(username)?;
+    let containers = db::get_standalone_containers(username)?;
+    let has_network = db::get_user_network(username).is_ok();
+    if !stacks.is_empty() || !containers.is_empty() || has_network {
+        let docker = Docker::connect_with_local_defaults()?;
+        for name in stacks {
+            let stack_containers = db::get_stack_containers(&name, username)?;
+            stack::remove_stack_resources(&docker, &name, &stack_containers).await;
+            db::delete_stack(&name)?;
+        }
+        for name in containers {
+            // Already removed containers shouldn't block deleting the account
+            if let Err(err) = container::delete_container_by_name(&docker, &name).await {
+                eprintln!("Error removing container {} of {}: {}", name, username, err);
+            }
+            db::delete_container(&name, username)?;
+        }
+        network::remove_user_network_if_unused(&docker, username).await;
+    }
+    db::delete_account(username, email)?;
+    Ok(())
+}
@@ -0,0 +1,152 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use axum::{
+    async_trait, body,
+    extract::{ConnectInfo, FromRequest, FromRequestParts, Request},
+    http::{request::Parts, StatusCode},
+};
+use dotenvy::var;
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde_json::from_slice;
+
+use crate::utils::{
+    orgs::{self, OrgRole},
+    res::{m_resp, Respond},
+    roles::{self, Permission, Role},
+    validation,
+};
+
+// Largest JSON body accepted by `JsonBody`
+pub const MAX_JSON_BODY: usize = 1024 * 1024;
+// Only enable behind a reverse proxy that appends the client to X-Forwarded-For,
+// otherwise clients can pick their own address
+static TRUST_FORWARDED_FOR: Lazy<bool> = Lazy::new(|| {
+    var("TRUST_FORWARDED_FOR")
+        .map(|value| value == "true")
+        .unwrap_or(false)
+});
+
+// The authenticated user making a request
+#[derive(Clone)]
+pub struct User {
+    pub username: String,
+    pub roles: Vec<Role>,
+    // Org given in the x-dockify-org header, if any
+    pub org: Option<String>,
+}
+
+impl User {
+    // The account the request acts on, see `orgs::resolve_account`
+    pub fn account(&self, required: OrgRole) -> Result<String, Respond> {
+        orgs::resolve_account(self.org.as_deref(), &self.username, required)
+    }
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.roles.iter().any(|role| role.grants(permission))
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for User
+where
+    S: Send + Sync,
+{
+    type Rejection = Respond;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        // Extractors built on this one share a single token check per request
+        if let Some(user) = parts.extensions.get::<User>() {
+            return Ok(user.clone());
+        }
+        let (validated, username) = validation::validate_request(&parts.headers).await;
+        if !validated {
+            return Err(m_resp(StatusCode::UNAUTHORIZED, "Invalid token"));
+        }
+        let roles = match roles::user_roles(&username) {
+            Ok(roles) => roles,
+            Err(err) => {
+                eprintln!("Error occurred while getting user's roles: {}", err);
+                return Err(m_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Please contact support for help.",
+                ));
+            }
+        };
+        let user = User {
+            username,
+            roles,
+            org: orgs::requested_org(&parts.headers).map(str::to_string),
+        };
+        parts.extensions.insert(user.clone());
+        Ok(user)
+    }
+}
+
+// JSON request body of at most MAX_JSON_BODY bytes
+pub struct JsonBody<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Respond;
+
+    async fn from_request(req: Request, _state: &S) -> Result<Self, Self::Rejection> {
+        let bytes = match body::to_bytes(req.into_body(), MAX_JSON_BODY).await {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                return Err(m_resp(
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    "Request body is too large.",
+                ))
+            }
+        };
+        match from_slice::<T>(&bytes) {
+            Ok(value) => Ok(JsonBody(value)),
+            Err(_) => Err(m_resp(
+                StatusCode::BAD_REQUEST,
+                "Failed to parse JSON from request body. Ensure the correct parameters are given.",
+            )),
+        }
+    }
+}
+
+// Address of the client making the request
+pub struct ClientIp(pub IpAddr);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = Respond;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if *TRUST_FORWARDED_FOR {
+            // The last entry is the one added by our proxy
+            let forwarded = parts
+                .headers
+                .get("x-forwarded-for")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.rsplit(',').next())
+                .and_then(|ip| ip.trim().parse().ok());
+            if let Some(ip) = forwarded {
+                return Ok(ClientIp(ip));
+            }
+        }
+        let ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        Ok(ClientIp(ip))
+    }
+}
@@ -0,0 +1,226 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::{collections::HashMap, error::Error, time::Duration};
+
+use bollard::{
+    container::{InspectContainerOptions, StatsOptions},
+    Docker,
+};
+use chrono::Utc;
+use dotenvy::var;
+use futures_util::StreamExt;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::utils::{config::PlanConfig, db};
+
+const GIGABYTE: i64 = 1024 * 1024 * 1024;
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct BandwidthPolicy {
+    // Egress rate limit for every container, in kbit/s
+    pub rate_kbit: Option<u64>,
+    // Monthly egress included in the plan, in bytes
+    pub included_transfer: i64,
+    // Credits charged for each started GB above the included transfer
+    pub overage_credits_per_gb: i64,
+    // Rate applied once a user can no longer pay for overage
+    pub throttle_rate_kbit: u64,
+}
+
+impl Default for BandwidthPolicy {
+    fn default() -> Self {
+        Self {
+            rate_kbit: None,
+            included_transfer: 100 * GIGABYTE,
+            overage_credits_per_gb: 1,
+            throttle_rate_kbit: 1024,
+        }
+    }
+}
+
+static CONFIG: Lazy<PlanConfig<BandwidthPolicy>> =
+    Lazy::new(|| PlanConfig::load("BANDWIDTH_CONFIG"));
+static SAMPLE_INTERVAL: Lazy<u64> = Lazy::new(|| {
+    var("BANDWIDTH_SAMPLE_INTERVAL")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(60)
+});
+
+pub fn plan_exists(plan: &str) -> bool {
+    CONFIG.has_plan(plan)
+}
+
+pub fn policy_for_user(username: &str) -> BandwidthPolicy {
+    CONFIG.for_user(username)
+}
+
+// Billing periods are calendar months in UTC
+pub fn current_period() -> String {
+    Utc::now().format("%Y-%m").to_string()
+}
+
+fn rate_for_user(username: &str) -> Option<u64> {
+    let policy = policy_for_user(username);
+    match db::is_egress_throttled(username, &current_period()) {
+        Ok(true) => Some(policy.throttle_rate_kbit),
+        Ok(false) => policy.rate_kbit,
+        Err(err) => {
+            eprintln!("Error while checking egress throttle: {}", err);
+            policy.rate_kbit
+        }
+    }
+}
+
+// Docker has no egress limit of its own, so a tbf qdisc is installed on every
+// interface inside the container's network namespace. The namespace is
+// recreated on each start, so this has to run after every start.
+async fn set_rate(
+    docker: &Docker,
+    container: &str,
+    rate_kbit: Option<u64>,
+) -> Result<(), Box<dyn Error>> {
+    let pid = docker
+        .inspect_container(container, None::<InspectContainerOptions>)
+        .await?
+        .state
+        .and_then(|state| state.pid)
+        .filter(|pid| *pid > 0)
+        .ok_or("Container is not running")?
+        .to_string();
+    let links = Command::new("nsenter")
+        .args(["-t", &pid, "-n", "ip", "-o", "link", "show"])
+        .output()
+        .await?;
+    let interfaces: Vec<String> = String::from_utf8_lossy(&links.stdout)
+        .lines()
+        .filter_map(|line| line.split(':').nth(1))
+        .map(|name| name.trim().split('@').next().unwrap_or("").to_string())
+        .filter(|name| !name.is_empty() && name != "lo")
+        .collect();
+    for interface in interfaces {
+        let qdisc = match rate_kbit {
+            Some(rate) => format!(
+                "replace dev {} root tbf rate {}kbit burst 32kbit latency 400ms",
+                interface, rate
+            ),
+            // Deleting a missing qdisc fails harmlessly
+            None => format!("del dev {} root", interface),
+        };
+        let mut args = vec!["-t", &pid, "-n", "tc", "qdisc"];
+        args.extend(qdisc.split(' '));
+        let status = Command::new("nsenter").args(&args).status().await?;
+        if !status.success() && rate_kbit.is_some() {
+            return Err(format!("tc exited with {}", status).into());
+        }
+    }
+    Ok(())
+}
+
+pub async fn apply_rate_limit(docker: &Docker, container: &str, username: &str) {
+    let rate = rate_for_user(username);
+    if rate.is_none() {
+        return;
+    }
+    if let Err(err) = set_rate(docker, container, rate).await {
+        eprintln!("Error applying rate limit to '{}': {}", container, err);
+    }
+}
+
+async fn apply_rate_to_user(docker: &Docker, username: &str) {
+    let rate = rate_for_user(username);
+    for container in db::get_user_containers(username).unwrap_or_default() {
+        let _ = set_rate(docker, &container.name, rate).await;
+    }
+}
+
+pub async fn run_usage_sampler() {
+    let mut interval = tokio::time::interval(Duration::from_secs(*SAMPLE_INTERVAL));
+    let mut last_period = current_period();
+    loop {
+        interval.tick().await;
+        let period = current_period();
+        if let Err(err) = sample_usage(&period, &last_period).await {
+            eprintln!("Error while sampling network usage: {}", err);
+        }
+        last_period = period;
+    }
+}
+
+async fn sample_usage(period: &str, last_period: &str) -> Result<(), Box<dyn Error>> {
+    let docker = Docker::connect_with_local_defaults()?;
+    // Throttles only last for the period they were applied in
+    if period != last_period {
+        for username in db::get_throttled_users(last_period)? {
+            apply_rate_to_user(&docker, &username).await;
+        }
+    }
+    let mut users: Vec<String> = Vec::new();
+    for (id, username) in db::get_all_container_owners()? {
+        let stats = docker
+            .stats(
+                &id,
+                Some(StatsOptions {
+                    stream: false,
+                    one_shot: true,
+                }),
+            )
+            .next()
+            .await;
+        let networks: HashMap<String, _> = match stats {
+            Some(Ok(stats)) => stats.networks.unwrap_or_default(),
+            _ => continue,
+        };
+        if networks.is_empty() {
+            continue;
+        }
+        let (rx, tx) = networks.values().fold((0, 0), |(rx, tx), network| {
+            (rx + network.rx_bytes as i64, tx + network.tx_bytes as i64)
+        });
+        db::record_network_usage(&id, &username, period, rx, tx)?;
+        if !users.contains(&username) {
+            users.push(username);
+        }
+    }
+    for username in users {
+        bill_egress(&docker, &username, period).await?;
+    }
+    Ok(())
+}
+
+// Charges each started GB above the plan's included transfer, throttling the
+// user's containers when they cannot pay
+async fn bill_egress(docker: &Docker, username: &str, period: &str) -> Result<(), Box<dyn Error>> {
+    let policy = policy_for_user(username);
+    let (_, tx) = db::get_user_network_usage(username, period)?;
+    let over = tx - policy.included_transfer;
+    if over <= 0 {
+        return Ok(());
+    }
+    let over_gb = (over + GIGABYTE - 1) / GIGABYTE;
+    let (charged_gb, throttled) = db::get_egress_billing(username, period)?;
+    if over_gb <= charged_gb {
+        return Ok(());
+    }
+    let cost = (over_gb - charged_gb) * policy.overage_credits_per_gb;
+    let credits = db::get_user_credits(username)?;
+    if credits >= cost {
+        db::set_user_credits(username, credits - cost)?;
+        db::set_egress_billing(username, period, over_gb, false)?;
+        if throttled {
+            apply_rate_to_user(docker, username).await;
+        }
+    } else if !throttled {
+        println!("{} ran out of credits for egress, throttling.", username);
+        db::set_egress_billing(username, period, charged_gb, true)?;
+        apply_rate_to_user(docker, username).await;
+    }
+    Ok(())
+}
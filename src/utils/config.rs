@@ -0,0 +1,75 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::{collections::HashMap, fs};
+
+use dotenvy::var;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::utils::db;
+
+// A default section plus plans that only list the fields they override,
+// e.g. { "plans": { "pro": { "pids_limit": 2048 } } }
+#[derive(Deserialize, Default)]
+#[serde(default, bound(deserialize = "T: DeserializeOwned + Default"))]
+pub struct PlanConfig<T> {
+    pub default: T,
+    pub plans: HashMap<String, Value>,
+}
+
+impl<T: Serialize + DeserializeOwned + Clone + Default> PlanConfig<T> {
+    // Reads the YAML or JSON file named by `env`, built-in defaults are used when unset
+    pub fn load(env: &str) -> Self {
+        match var(env) {
+            Ok(path) => {
+                let contents = fs::read_to_string(&path)
+                    .unwrap_or_else(|err| panic!("Failed to read {}: {}", env, err));
+                serde_norway::from_str(&contents)
+                    .unwrap_or_else(|err| panic!("Failed to parse {}: {}", env, err))
+            }
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn has_plan(&self, plan: &str) -> bool {
+        self.plans.contains_key(plan)
+    }
+
+    pub fn for_plan(&self, plan: Option<&str>) -> T {
+        let default = self.default.clone();
+        let overrides = match plan.and_then(|plan| self.plans.get(plan)) {
+            Some(Value::Object(overrides)) => overrides,
+            _ => return default,
+        };
+        let mut merged = match serde_json::to_value(&default) {
+            Ok(Value::Object(map)) => map,
+            _ => return default,
+        };
+        for (key, value) in overrides {
+            merged.insert(key.clone(), value.clone());
+        }
+        match serde_json::from_value(Value::Object(merged)) {
+            Ok(config) => config,
+            Err(err) => {
+                eprintln!("Invalid override for plan {:?}: {}", plan, err);
+                default
+            }
+        }
+    }
+
+    pub fn for_user(&self, username: &str) -> T {
+        match db::get_user_plan(username) {
+            Ok(plan) => self.for_plan(plan.as_deref()),
+            // Org accounts aren't users and always get the default plan
+            Err(rusqlite::Error::QueryReturnedNoRows) => self.for_plan(None),
+            Err(err) => {
+                eprintln!("Error while getting user plan: {}", err);
+                self.for_plan(None)
+            }
+        }
+    }
+}
@@ -4,26 +4,33 @@
     Find the LICENSE file in the root of this repository for more details.
 */
 
-use std::{collections::HashMap, future::Future, net::TcpListener};
+use std::{collections::HashMap, error::Error as StdError, future::Future, net::TcpListener};
 
 use axum::http::StatusCode;
 use bollard::{
     container::{
-        Config, CreateContainerOptions, RemoveContainerOptions, StartContainerOptions,
-        StopContainerOptions,
+        Config, CreateContainerOptions, InspectContainerOptions, ListContainersOptions,
+        NetworkingConfig, RemoveContainerOptions, StartContainerOptions, StopContainerOptions,
     },
     errors::Error,
     secret::{HostConfig, PortBinding},
     Docker,
 };
-use serde::Deserialize;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    routes::container::create::ContainerInfo,
+    routes::container::create::{ContainerInfo, DEFAULT_IMAGE},
     utils::{
+        bandwidth,
         db::{self},
+        hardening::{self, HardeningProfile},
+        health::{self, HealthCheck},
+        network,
         res::{m_resp, GenericResponse},
         resources::ContainerResources,
+        secrets, storage,
     },
 };
 
@@ -34,10 +41,84 @@ pub struct ContainerName {
     pub name: String,
 }
 
+static USER_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[A-Za-z0-9_.-]{1,64}(:[A-Za-z0-9_.-]{1,64})?$").unwrap());
+static LABEL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[A-Za-z0-9][A-Za-z0-9._/-]{0,127}$").unwrap());
+// Labels under this prefix are used by Dockify itself
+pub const RESERVED_LABEL_PREFIX: &str = "dockify.";
+const MAX_ARGS: usize = 64;
+const MAX_ARG_LEN: usize = 4096;
+const MAX_LABELS: usize = 32;
+const MAX_LABEL_VALUE_LEN: usize = 1024;
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct RuntimeOptions {
+    pub cmd: Option<Vec<String>>,
+    pub entrypoint: Option<Vec<String>>,
+    pub working_dir: Option<String>,
+    pub user: Option<String>,
+    pub labels: Option<HashMap<String, String>>,
+    pub healthcheck: Option<HealthCheck>,
+}
+
+impl RuntimeOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        for (field, args) in [("cmd", &self.cmd), ("entrypoint", &self.entrypoint)] {
+            let Some(args) = args else { continue };
+            if args.len() > MAX_ARGS {
+                return Err(format!(
+                    "{} can have at most {} arguments.",
+                    field, MAX_ARGS
+                ));
+            }
+            if args
+                .iter()
+                .any(|arg| arg.len() > MAX_ARG_LEN || arg.contains('\0'))
+            {
+                return Err(format!("{} contains an invalid argument.", field));
+            }
+        }
+        if let Some(dir) = &self.working_dir {
+            if !dir.starts_with('/') || dir.len() > MAX_ARG_LEN || dir.contains('\0') {
+                return Err("working_dir must be an absolute path.".to_string());
+            }
+        }
+        if let Some(user) = &self.user {
+            if !USER_REGEX.is_match(user) {
+                return Err("user must be in the form user[:group].".to_string());
+            }
+        }
+        if let Some(labels) = &self.labels {
+            if labels.len() > MAX_LABELS {
+                return Err(format!(
+                    "Containers can have at most {} labels.",
+                    MAX_LABELS
+                ));
+            }
+            for (key, value) in labels {
+                if !LABEL_REGEX.is_match(key)
+                    || key.starts_with(RESERVED_LABEL_PREFIX)
+                    || value.len() > MAX_LABEL_VALUE_LEN
+                {
+                    return Err(format!("Invalid label: {}", key));
+                }
+            }
+        }
+        if let Some(healthcheck) = &self.healthcheck {
+            healthcheck.validate()?;
+        }
+        Ok(())
+    }
+}
+
 pub fn create_config(
     ports: HashMap<u16, u16>,
     resources: ContainerResources,
     image: impl Into<String>,
+    env: Vec<String>,
+    options: RuntimeOptions,
+    profile: &HardeningProfile,
 ) -> Config<String> {
     let mut port_bindings = HashMap::new();
     for port in &ports {
@@ -53,18 +134,28 @@ pub fn create_config(
         .keys()
         .map(|exposed| (format!("{}/tcp", exposed), HashMap::new()))
         .collect();
+    let mut host_config = HostConfig {
+        port_bindings: Some(port_bindings),
+        cpu_shares: Some(resources.cpu_shares),
+        memory: Some(resources.memory),
+        memory_swap: Some(resources.memory_swap),
+        nano_cpus: Some(resources.cpu_cores * 1_000_000_000),
+        ..Default::default()
+    };
+    hardening::apply(profile, &mut host_config);
     Config {
         image: Some(image.into()),
+        env: Some(env),
         exposed_ports: Some(exposed_ports),
-        host_config: Some(HostConfig {
-            port_bindings: Some(port_bindings),
-            cpu_shares: Some(resources.cpu_shares),
-            memory: Some(resources.memory),
-            memory_swap: Some(resources.memory_swap),
-            nano_cpus: Some(resources.cpu_cores * 1_000_000_000),
-            ..Default::default()
-        }),
-        labels: None,
+        host_config: Some(host_config),
+        cmd: options.cmd,
+        entrypoint: options.entrypoint,
+        working_dir: options.working_dir,
+        user: options.user,
+        labels: options.labels,
+        healthcheck: options
+            .healthcheck
+            .map(|healthcheck| healthcheck.to_config()),
         ..Default::default()
     }
 }
@@ -76,20 +167,26 @@ pub fn check_user_resources(id: impl Into<String>) -> Result<ContainerResources,
         memory: 0,
         memory_swap: 0,
         cpu_shares: 0,
+        disk: 0,
     };
     for container in containers {
         resources.cpu_cores += container.cpu_cores;
         resources.memory += container.memory;
         resources.memory_swap += container.memory_swap;
         resources.cpu_shares += container.cpu_shares;
+        resources.disk += container.disk.unwrap_or(0);
     }
     Ok(ContainerResources {
         cpu_cores: resources.cpu_cores,
         memory: resources.memory,
         cpu_shares: resources.memory_swap,
         memory_swap: resources.cpu_shares,
+        disk: resources.disk,
     })
 }
+// TODO: Create user-specific container counts
+pub const MAX_CONTAINERS: i32 = 2;
+
 pub fn user_container_count(id: &String) -> Result<i32, Respond> {
     Ok(match db::count_containers_by_username(&id) {
         Ok(count) => count,
@@ -121,13 +218,84 @@ pub fn validate_container_resources(
         return Ok(false);
     }
 }
+const MAX_ENV_VARS: usize = 64;
+const MAX_ENV_VALUE_LEN: usize = 32 * 1024;
+
+// Builds the container's KEY=VALUE list, decrypting any referenced secrets
+pub fn resolve_env(
+    username: &str,
+    vars: &HashMap<String, String>,
+    secret_refs: &HashMap<String, String>,
+) -> Result<Vec<String>, Respond> {
+    if vars.len() + secret_refs.len() > MAX_ENV_VARS {
+        return Err(m_resp(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Containers can have at most {} environment variables.",
+                MAX_ENV_VARS
+            ),
+        ));
+    }
+    let mut env = Vec::new();
+    for (key, value) in vars {
+        if !secrets::validate_name(key) || value.len() > MAX_ENV_VALUE_LEN {
+            return Err(m_resp(
+                StatusCode::BAD_REQUEST,
+                format!("Invalid environment variable: {}", key),
+            ));
+        }
+        env.push(format!("{}={}", key, value));
+    }
+    for (key, secret_name) in secret_refs {
+        if !secrets::validate_name(key) || vars.contains_key(key) {
+            return Err(m_resp(
+                StatusCode::BAD_REQUEST,
+                format!("Invalid environment variable: {}", key),
+            ));
+        }
+        let value = match db::get_secret(username, secret_name) {
+            Ok((nonce, ciphertext)) => match secrets::decrypt(&nonce, &ciphertext) {
+                Ok(value) => value,
+                Err(_) => {
+                    eprintln!("Failed to decrypt secret '{}' of {}", secret_name, username);
+                    return Err(m_resp(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Please contact support for help.",
+                    ));
+                }
+            },
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                return Err(m_resp(
+                    StatusCode::BAD_REQUEST,
+                    format!("No secret found with name: {}", secret_name),
+                ))
+            }
+            Err(err) => {
+                eprintln!("Error while getting user secret: {}", err);
+                return Err(m_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Please contact support for help.",
+                ));
+            }
+        };
+        env.push(format!("{}={}", key, value));
+    }
+    Ok(env)
+}
+
 pub fn get_available_port() -> Option<u16> {
-    (59001..60000).find(|&port| TcpListener::bind(("127.0.0.1", port)).is_ok())
+    get_available_port_excluding(&[])
+}
+// Used when several ports are picked before any of them are bound
+pub fn get_available_port_excluding(taken: &[u16]) -> Option<u16> {
+    (59001..60000)
+        .find(|port| !taken.contains(port) && TcpListener::bind(("127.0.0.1", *port)).is_ok())
 }
 
 pub fn create_container(
     resources: ContainerResources,
     container_info: ContainerInfo,
+    env: Vec<String>,
     name: String,
     username: String,
 ) -> impl Future<Output = Respond> {
@@ -151,9 +319,34 @@ pub fn create_container(
                 )
             }
         };
+        let user_network = match network::ensure_user_network(&docker, &username).await {
+            Ok(network) => network,
+            Err(err) => {
+                eprintln!("Error creating user network: {}", err);
+                return m_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed while creating user network.",
+                );
+            }
+        };
         let mut ports: HashMap<u16, u16> = HashMap::new();
         ports.insert(80, container_port);
-        let config = create_config(ports, resources, container_info.image);
+        let disk = resources.disk;
+        let mut config = create_config(
+            ports,
+            resources,
+            container_info.image,
+            env,
+            container_info.options,
+            &hardening::profile_for_user(&username),
+        );
+        if let Some(host_config) = config.host_config.as_mut() {
+            host_config.network_mode = Some(user_network.clone());
+            storage::apply_quota(&docker, host_config, disk).await;
+        }
+        config.networking_config = Some(NetworkingConfig {
+            endpoints_config: HashMap::from([(user_network, network::endpoint(&name))]),
+        });
         let create_options = CreateContainerOptions {
             name: &name,
             platform: None,
@@ -189,8 +382,19 @@ pub fn create_container(
         }
 
         println!("Container started successfully.");
+        bandwidth::apply_rate_limit(&docker, &name, &username).await;
 
-        match db::insert_container(&container.id, username, name, config, container_port).await {
+        match db::insert_container(
+            &container.id,
+            username,
+            name,
+            config,
+            container_port,
+            None,
+            disk,
+        )
+        .await
+        {
             Ok(updated) if updated > 0 => Respond::Generic(
                 StatusCode::OK,
                 GenericResponse::Container {
@@ -210,6 +414,81 @@ pub fn create_container(
     };
 }
 
+// Creates the container again from its stored configuration, e.g. to pick up a newer
+// version of its image. Environment variables and the healthcheck only live in Docker,
+// so they are carried over from the old container when it still exists.
+pub async fn recreate_container(name: &str, username: &str) -> Result<(), Box<dyn StdError>> {
+    let docker = Docker::connect_with_local_defaults()?;
+    let container = db::get_user_containers(username)?
+        .into_iter()
+        .find(|container| container.name == name)
+        .ok_or("Container not found")?;
+    let options = db::get_container_options(name)?;
+    let (env, healthcheck) = match docker
+        .inspect_container(name, None::<InspectContainerOptions>)
+        .await
+    {
+        Ok(inspect) => {
+            let config = inspect.config.unwrap_or_default();
+            (config.env.unwrap_or_default(), config.healthcheck)
+        }
+        Err(Error::DockerResponseServerError {
+            status_code: 404, ..
+        }) => (Vec::new(), None),
+        Err(err) => return Err(err.into()),
+    };
+    let port = container.port as u16;
+    let resources = ContainerResources {
+        // Stored as nano CPUs
+        cpu_cores: container.cpu_cores / 1_000_000_000,
+        memory: container.memory,
+        memory_swap: container.memory_swap,
+        cpu_shares: container.cpu_shares,
+        disk: container.disk.unwrap_or(0),
+    };
+    let user_network = network::ensure_user_network(&docker, username).await?;
+    let mut config = create_config(
+        HashMap::from([(80, port)]),
+        resources,
+        container.image.unwrap_or_else(|| DEFAULT_IMAGE.to_string()),
+        env,
+        options,
+        &hardening::profile_for_user(username),
+    );
+    config.healthcheck = healthcheck;
+    if let Some(host_config) = config.host_config.as_mut() {
+        host_config.network_mode = Some(user_network.clone());
+        storage::apply_quota(&docker, host_config, container.disk.unwrap_or(0)).await;
+    }
+    config.networking_config = Some(NetworkingConfig {
+        endpoints_config: HashMap::from([(user_network, network::endpoint(name))]),
+    });
+    match delete_container_by_name(&docker, name).await {
+        Ok(_)
+        | Err(Error::DockerResponseServerError {
+            status_code: 404, ..
+        }) => (),
+        Err(err) => return Err(err.into()),
+    }
+    let created = docker
+        .create_container(
+            Some(CreateContainerOptions {
+                name,
+                platform: None,
+            }),
+            config,
+        )
+        .await?;
+    db::set_container_id(name, &created.id)?;
+    docker
+        .start_container(&created.id, None::<StartContainerOptions<String>>)
+        .await?;
+    db::set_sleeping(name, false)?;
+    bandwidth::apply_rate_limit(&docker, name, username).await;
+    println!("Recreated container '{}' of {}", name, username);
+    Ok(())
+}
+
 pub async fn delete_container_by_name(docker: &Docker, container_name: &str) -> Result<(), Error> {
     let remove_options = Some(RemoveContainerOptions {
         force: true, // Force remove if running
@@ -232,8 +511,12 @@ pub fn container_exists(containers: &Vec<Container>, search_name: &str) -> bool
 pub async fn start_container(name: &str) -> Result<(), Error> {
     let docker = Docker::connect_with_local_defaults()?;
 
+    // Stopped containers are only listed with `all`
     let containers = docker
-        .list_containers::<String>(None)
+        .list_containers(Some(ListContainersOptions::<String> {
+            all: true,
+            ..Default::default()
+        }))
         .await?
         .into_iter()
         .filter(|container| {
@@ -284,6 +567,9 @@ pub async fn stop_container(name: &str) -> Result<(), Error> {
         docker
             .stop_container(&container_id.clone().unwrap(), Some(options))
             .await?;
+        if let Err(err) = db::set_container_health(name, Some(health::STOPPED)) {
+            eprintln!("Error updating container health: {}", err);
+        }
         Ok(())
     } else {
         println!("No container found with name: {}", name);
@@ -6,8 +6,10 @@
 
 use bollard::container::Config;
 use rusqlite::{params, Connection, Result};
-use serde::Serialize;
-use std::{fs::File, path::Path};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{collections::HashMap, fs::File, path::Path};
+
+use crate::utils::container::RuntimeOptions;
 
 pub async fn insert_container(
     id: &String,
@@ -15,15 +17,20 @@ pub async fn insert_container(
     name: String,
     config: Config<String>,
     port: u16,
+    stack: Option<&str>,
+    disk: i64,
 ) -> Result<usize, rusqlite::Error> {
     let conn = Connection::open("./dockify.db")?;
-    let config = match config.host_config {
+    let cmd = to_json(&config.cmd);
+    let entrypoint = to_json(&config.entrypoint);
+    let labels = to_json(&config.labels);
+    let host_config = match config.host_config {
         Some(x) => x,
         None => return Ok(0),
     };
     match conn.execute(
-        "INSERT INTO containers (id, username, name, memory, memory_swap, cpu_cores, cpu_shares, port) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-        params![id, username, name, config.memory, config.memory_swap, config.nano_cpus, config.cpu_shares, port],
+        "INSERT INTO containers (id, username, name, memory, memory_swap, cpu_cores, cpu_shares, port, image, cmd, entrypoint, working_dir, user, labels, stack, disk) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+        params![id, username, name, host_config.memory, host_config.memory_swap, host_config.nano_cpus, host_config.cpu_shares, port, config.image, cmd, entrypoint, config.working_dir, config.user, labels, stack, disk],
     ) {
         Ok(updated) => {
             println!("{} rows were updated", updated);
@@ -35,17 +42,30 @@ pub async fn insert_container(
         }
     }
 }
+fn to_json<T: Serialize>(value: &Option<T>) -> Option<String> {
+    value
+        .as_ref()
+        .and_then(|value| serde_json::to_string(value).ok())
+}
+fn from_json<T: DeserializeOwned>(value: Option<String>) -> Option<T> {
+    value.and_then(|value| serde_json::from_str(&value).ok())
+}
 
+// Runs on every start so tables and columns added by updates also reach existing databases
 pub async fn create_db() {
-    if Path::new("./dockify.db").exists() {
-        return;
-    }
-    println!("Creating Dockify database file...");
-    match File::create("dockify.db") {
-        Ok(_) => {
-            let conn = Connection::open("./dockify.db").unwrap();
-            let stmts = [
-                "CREATE TABLE IF NOT EXISTS containers (
+    if !Path::new("./dockify.db").exists() {
+        println!("Creating Dockify database file...");
+        if let Err(err) = File::create("dockify.db") {
+            eprintln!("An error occurred while creating Dockify DataBase: {}", err);
+            return;
+        }
+    }
+    let conn = Connection::open("./dockify.db").unwrap();
+    if let Err(err) = drop_replaced_tables(&conn) {
+        eprintln!("Error while dropping replaced tables: {}", err);
+    }
+    let stmts = [
+        "CREATE TABLE IF NOT EXISTS containers (
                     id TEXT PRIMARY KEY UNIQUE NOT NULL,
                     username TEXT NOT NULL,
                     name TEXT NOT NULL,
@@ -53,45 +73,298 @@ pub async fn create_db() {
                     memory_swap INTEGER NOT NULL,
                     cpu_cores INTEGER NOT NULL,
                     cpu_shares INTEGER NOT NULL,
-                    port INTEGER NOT NULL
+                    port INTEGER NOT NULL,
+                    image TEXT,
+                    cmd TEXT,
+                    entrypoint TEXT,
+                    working_dir TEXT,
+                    user TEXT,
+                    labels TEXT,
+                    stack TEXT,
+                    disk INTEGER,
+                    disk_warned INTEGER NOT NULL DEFAULT 0,
+                    sleeping INTEGER NOT NULL DEFAULT 0,
+                    health TEXT
                 )",
-                "CREATE TABLE IF NOT EXISTS users (
+        "CREATE TABLE IF NOT EXISTS users (
                     email TEXT PRIMARY KEY UNIQUE NOT NULL,
                     username TEXT UNIQUE NOT NULL,
                     dusername TEXT UNIQUE NOT NULL,
                     hash TEXT NOT NULL,
                     verified INTEGER NOT NULL,
                     max INTEGER,
-                    admin INTEGER
+                    admin INTEGER,
+                    plan TEXT,
+                    created INTEGER
                 )",
-                "CREATE TABLE IF NOT EXISTS ip_logs (
-                    username TEXT PRIMARY KEY UNIQUE NOT NULL,
-                    ip TEXT NOT NULL
+        "CREATE TABLE IF NOT EXISTS ip_logs (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    username TEXT NOT NULL,
+                    ip TEXT NOT NULL,
+                    user_agent TEXT,
+                    method TEXT NOT NULL,
+                    success INTEGER NOT NULL,
+                    created INTEGER NOT NULL
                 )",
-                "CREATE TABLE IF NOT EXISTS verification_codes (
+        "CREATE TABLE IF NOT EXISTS verification_codes (
                     verification_code TEXT PRIMARY KEY UNIQUE NOT NULL,
-                    username TEXT NOT NULL
+                    username TEXT NOT NULL,
+                    created INTEGER,
+                    expires INTEGER
+                )",
+        "CREATE TABLE IF NOT EXISTS sessions (
+                    id TEXT PRIMARY KEY NOT NULL,
+                    username TEXT NOT NULL,
+                    refresh_hash TEXT UNIQUE NOT NULL,
+                    previous_hash TEXT,
+                    created INTEGER NOT NULL,
+                    expires INTEGER NOT NULL,
+                    revoked INTEGER NOT NULL DEFAULT 0
+                )",
+        "CREATE TABLE IF NOT EXISTS api_tokens (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    username TEXT NOT NULL,
+                    name TEXT NOT NULL,
+                    token_hash TEXT UNIQUE NOT NULL,
+                    scopes TEXT NOT NULL,
+                    created INTEGER NOT NULL,
+                    expires INTEGER,
+                    last_used INTEGER
+                )",
+        "CREATE TABLE IF NOT EXISTS oidc_states (
+                    state TEXT PRIMARY KEY NOT NULL,
+                    nonce TEXT NOT NULL,
+                    verifier TEXT NOT NULL,
+                    created INTEGER NOT NULL
+                )",
+        "CREATE TABLE IF NOT EXISTS oidc_identities (
+                    issuer TEXT NOT NULL,
+                    subject TEXT NOT NULL,
+                    username TEXT NOT NULL,
+                    PRIMARY KEY (issuer, subject)
+                )",
+        "CREATE TABLE IF NOT EXISTS user_roles (
+                    username TEXT NOT NULL,
+                    role TEXT NOT NULL,
+                    granted_by TEXT NOT NULL,
+                    granted INTEGER NOT NULL,
+                    PRIMARY KEY (username, role)
+                )",
+        "CREATE TABLE IF NOT EXISTS audit_log (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    actor TEXT NOT NULL,
+                    action TEXT NOT NULL,
+                    target TEXT NOT NULL,
+                    detail TEXT,
+                    time INTEGER NOT NULL
+                )",
+        "CREATE TABLE IF NOT EXISTS orgs (
+                    slug TEXT PRIMARY KEY NOT NULL,
+                    name TEXT NOT NULL,
+                    created INTEGER NOT NULL
+                )",
+        "CREATE TABLE IF NOT EXISTS org_members (
+                    org TEXT NOT NULL,
+                    username TEXT NOT NULL,
+                    role TEXT NOT NULL,
+                    joined INTEGER NOT NULL,
+                    PRIMARY KEY (org, username)
+                )",
+        "CREATE TABLE IF NOT EXISTS org_invites (
+                    token_hash TEXT PRIMARY KEY NOT NULL,
+                    org TEXT NOT NULL,
+                    email TEXT NOT NULL,
+                    role TEXT NOT NULL,
+                    invited_by TEXT NOT NULL,
+                    expires INTEGER NOT NULL
+                )",
+        "CREATE TABLE IF NOT EXISTS settings (
+                    key TEXT PRIMARY KEY NOT NULL,
+                    value TEXT NOT NULL
+                )",
+        "CREATE TABLE IF NOT EXISTS signup_invites (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    code_hash TEXT UNIQUE NOT NULL,
+                    created_by TEXT NOT NULL,
+                    max_uses INTEGER NOT NULL,
+                    uses INTEGER NOT NULL DEFAULT 0,
+                    expires INTEGER,
+                    created INTEGER NOT NULL
+                )",
+        "CREATE TABLE IF NOT EXISTS login_failures (
+                    key TEXT PRIMARY KEY NOT NULL,
+                    failures INTEGER NOT NULL,
+                    locked_until INTEGER NOT NULL,
+                    last_failure INTEGER NOT NULL
+                )",
+        "CREATE TABLE IF NOT EXISTS mfa (
+                    username TEXT PRIMARY KEY NOT NULL,
+                    nonce TEXT NOT NULL,
+                    secret TEXT NOT NULL,
+                    enabled INTEGER NOT NULL DEFAULT 0,
+                    last_step INTEGER
+                )",
+        "CREATE TABLE IF NOT EXISTS recovery_codes (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    username TEXT NOT NULL,
+                    hash TEXT NOT NULL
+                )",
+        "CREATE TABLE IF NOT EXISTS password_resets (
+                    token_hash TEXT PRIMARY KEY NOT NULL,
+                    username TEXT NOT NULL,
+                    expires INTEGER NOT NULL
+                )",
+        "CREATE TABLE IF NOT EXISTS email_changes (
+                    token_hash TEXT PRIMARY KEY NOT NULL,
+                    username TEXT NOT NULL,
+                    email TEXT NOT NULL,
+                    expires INTEGER NOT NULL
                 )",
-                "CREATE TABLE IF NOT EXISTS credits (
+        "CREATE TABLE IF NOT EXISTS credits (
                     username TEXT PRIMARY KEY UNIQUE NOT NULL,
                     credits INTEGER NOT NULL
                 )",
-            ];
-            for stmt in stmts {
-                match conn.execute(stmt, []) {
-                    Ok(_) => {
-                        println!("Created new table")
-                    }
-                    Err(err) => {
-                        tracing::error!("Error while creating table: {}", err)
-                    }
-                }
-            }
+        "CREATE TABLE IF NOT EXISTS secrets (
+                    username TEXT NOT NULL,
+                    name TEXT NOT NULL,
+                    nonce TEXT NOT NULL,
+                    value TEXT NOT NULL,
+                    PRIMARY KEY (username, name)
+                )",
+        "CREATE TABLE IF NOT EXISTS user_networks (
+                    username TEXT PRIMARY KEY UNIQUE NOT NULL,
+                    network TEXT NOT NULL
+                )",
+        "CREATE TABLE IF NOT EXISTS network_counters (
+                    id TEXT PRIMARY KEY UNIQUE NOT NULL,
+                    last_rx INTEGER NOT NULL,
+                    last_tx INTEGER NOT NULL
+                )",
+        "CREATE TABLE IF NOT EXISTS network_usage (
+                    id TEXT NOT NULL,
+                    username TEXT NOT NULL,
+                    period TEXT NOT NULL,
+                    rx INTEGER NOT NULL,
+                    tx INTEGER NOT NULL,
+                    PRIMARY KEY (id, period)
+                )",
+        "CREATE TABLE IF NOT EXISTS egress_billing (
+                    username TEXT NOT NULL,
+                    period TEXT NOT NULL,
+                    charged_gb INTEGER NOT NULL,
+                    throttled INTEGER NOT NULL,
+                    PRIMARY KEY (username, period)
+                )",
+        "CREATE TABLE IF NOT EXISTS container_events (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    username TEXT NOT NULL,
+                    container TEXT NOT NULL,
+                    action TEXT NOT NULL,
+                    exit_code INTEGER,
+                    time INTEGER NOT NULL
+                )",
+        "CREATE INDEX IF NOT EXISTS container_events_username ON container_events (username, id)",
+        "CREATE TABLE IF NOT EXISTS notification_settings (
+                    username TEXT PRIMARY KEY NOT NULL,
+                    email INTEGER NOT NULL DEFAULT 1,
+                    webhook TEXT
+                )",
+        "CREATE TABLE IF NOT EXISTS schedules (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    username TEXT NOT NULL,
+                    container TEXT NOT NULL,
+                    action TEXT NOT NULL,
+                    cron TEXT NOT NULL,
+                    last_run INTEGER,
+                    next_run INTEGER
+                )",
+        "CREATE TABLE IF NOT EXISTS stacks (
+                    name TEXT PRIMARY KEY UNIQUE NOT NULL,
+                    username TEXT NOT NULL,
+                    network TEXT NOT NULL,
+                    spec TEXT NOT NULL
+                )",
+    ];
+    for stmt in stmts {
+        if let Err(err) = conn.execute(stmt, []) {
+            eprintln!("Error while creating table: {}", err);
         }
-        Err(err) => {
-            tracing::error!("An error occurred while creating Dockify DataBase: {}", err);
+    }
+    if let Err(err) = migrate(&conn) {
+        eprintln!("Error while migrating database: {}", err);
+    }
+}
+// Columns added to a table after it was released, as (table, column, definition)
+const ADDED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("containers", "image", "TEXT"),
+    ("containers", "cmd", "TEXT"),
+    ("containers", "entrypoint", "TEXT"),
+    ("containers", "working_dir", "TEXT"),
+    ("containers", "user", "TEXT"),
+    ("containers", "labels", "TEXT"),
+    ("containers", "stack", "TEXT"),
+    ("users", "plan", "TEXT"),
+    ("containers", "disk", "INTEGER"),
+    ("containers", "disk_warned", "INTEGER NOT NULL DEFAULT 0"),
+    ("containers", "sleeping", "INTEGER NOT NULL DEFAULT 0"),
+    ("containers", "health", "TEXT"),
+    ("users", "created", "INTEGER"),
+    ("verification_codes", "created", "INTEGER"),
+    ("verification_codes", "expires", "INTEGER"),
+];
+// Tables whose old layout was never written to, as (table, column only the new layout
+// has). They are dropped so create_db recreates them.
+const REPLACED_TABLES: &[(&str, &str)] = &[("ip_logs", "id")];
+fn drop_replaced_tables(conn: &Connection) -> Result<()> {
+    for (table, column) in REPLACED_TABLES {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+            params![table],
+            |row| row.get(0),
+        )?;
+        if exists && !has_column(conn, table, column)? {
+            conn.execute(&format!("DROP TABLE {}", table), [])?;
+            println!("Dropped old table {}", table);
+        }
+    }
+    Ok(())
+}
+// Columns that are no longer used, as (table, column)
+const REMOVED_COLUMNS: &[(&str, &str)] = &[("users", "sessions_valid_after")];
+fn migrate(conn: &Connection) -> Result<()> {
+    for (table, column) in REMOVED_COLUMNS {
+        if has_column(conn, table, column)? {
+            conn.execute(&format!("ALTER TABLE {} DROP COLUMN {}", table, column), [])?;
+            println!("Removed column {}.{}", table, column);
+        }
+    }
+    for (table, column, definition) in ADDED_COLUMNS {
+        if !has_column(conn, table, column)? {
+            conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+                [],
+            )?;
+            println!("Added column {}.{}", table, column);
         }
     }
+    // The legacy admin flag becomes the super admin role, so it can be revoked like any other
+    let promoted = conn.execute(
+        "INSERT OR IGNORE INTO user_roles (username, role, granted_by, granted)
+            SELECT username, 'super_admin', 'system', ?1 FROM users WHERE admin = 1",
+        params![chrono::Utc::now().timestamp()],
+    )?;
+    conn.execute("UPDATE users SET admin = 0 WHERE admin = 1", [])?;
+    if promoted > 0 {
+        println!("Granted super admin to {} legacy admins", promoted);
+    }
+    Ok(())
+}
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<String>>>()?;
+    Ok(columns.iter().any(|name| name == column))
 }
 pub fn check_exists(
     row: impl Into<String>,
@@ -111,13 +384,55 @@ pub fn check_exists(
 
     Ok(exists)
 }
-pub fn insert_code(username: &str, code: &str) -> Result<()> {
+pub fn insert_code(username: &str, code: &str, expires: i64) -> Result<()> {
     Connection::open("./dockify.db")?.execute(
-        "INSERT INTO verification_codes (username, verification_code) VALUES (?1, ?2)",
-        params![username, code],
+        "INSERT INTO verification_codes (username, verification_code, created, expires) VALUES (?1, ?2, ?3, ?4)",
+        params![username, code, chrono::Utc::now().timestamp(), expires],
     )?;
     Ok(())
 }
+// Creation times of the user's codes sent after `since`, newest first
+pub fn get_recent_codes(username: &str, since: i64) -> Result<Vec<i64>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare(
+        "SELECT created FROM verification_codes WHERE username = ?1 AND created >= ?2 ORDER BY created DESC",
+    )?;
+    let codes = stmt
+        .query_map(params![username, since], |row| row.get(0))?
+        .collect::<Result<Vec<i64>>>()?;
+    Ok(codes)
+}
+// Codes issued before expiry was tracked have no expiry and are treated as expired
+pub fn purge_expired_codes(now: i64) -> Result<usize> {
+    Connection::open("./dockify.db")?.execute(
+        "DELETE FROM verification_codes WHERE expires IS NULL OR expires < ?1",
+        params![now],
+    )
+}
+// Accounts created before the cutoff that never verified their email.
+// Accounts from before creation time was tracked are past any cutoff.
+pub fn delete_unverified_users(created_before: i64) -> Result<Vec<String>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare(
+        "DELETE FROM users WHERE verified = 0 AND (created IS NULL OR created < ?1) RETURNING username",
+    )?;
+    let usernames = stmt
+        .query_map(params![created_before], |row| row.get(0))?
+        .collect::<Result<Vec<String>>>()?;
+    for username in &usernames {
+        conn.execute(
+            "DELETE FROM verification_codes WHERE username = ?1",
+            params![username],
+        )?;
+        conn.execute("DELETE FROM credits WHERE username = ?1", params![username])?;
+        // Otherwise whoever signs up with the name next would inherit them
+        conn.execute(
+            "DELETE FROM user_roles WHERE username = ?1",
+            params![username],
+        )?;
+    }
+    Ok(usernames)
+}
 pub fn remove_code(code: &str) -> Result<()> {
     Connection::open("./dockify.db")?.execute(
         "DELETE FROM verification_codes WHERE verification_code = ?1",
@@ -128,8 +443,8 @@ pub fn remove_code(code: &str) -> Result<()> {
 
 pub fn insert_user(email: &str, username: &str, hash: &str, verified: bool) -> Result<()> {
     Connection::open("./dockify.db")?.execute(
-        "INSERT INTO users (email, username, hash, verified, dusername) VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![email.to_lowercase(), username.to_lowercase(), hash, verified, username],
+        "INSERT INTO users (email, username, hash, verified, dusername, created) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![email.to_lowercase(), username.to_lowercase(), hash, verified, username, chrono::Utc::now().timestamp()],
     )?;
     Ok(())
 }
@@ -145,6 +460,13 @@ pub fn verify_user(username: &str) -> Result<(), rusqlite::Error> {
     Ok(())
 }
 
+pub fn get_user_by_email(email: &str) -> Result<(i32, String)> {
+    Connection::open("./dockify.db")?.query_row(
+        "SELECT verified, username FROM users WHERE email = ?1",
+        params![email.to_lowercase()],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+}
 pub fn get_user_info(identifier: &str) -> Result<(String, i32, String, String)> {
     let conn = Connection::open("./dockify.db")?;
 
@@ -164,20 +486,17 @@ pub fn get_user_info(identifier: &str) -> Result<(String, i32, String, String)>
         Err(rusqlite::Error::QueryReturnedNoRows)
     }
 }
-pub fn is_admin(username: impl Into<String>) -> Result<bool> {
+pub fn get_user_plan(username: &str) -> Result<Option<String>> {
     let conn = Connection::open("./dockify.db")?;
-
-    let mut stmt = conn.prepare("SELECT admin FROM users WHERE username = ?1")?;
-
-    let admin: Option<i64> = stmt
-        .query_row(params![username.into()], |row| row.get(0))
-        .ok();
-
-    Ok(match admin {
-        Some(1) => true,
-        Some(_) => false,
-        None => false,
-    })
+    let mut stmt = conn.prepare("SELECT plan FROM users WHERE username = ?1")?;
+    stmt.query_row(params![username], |row| row.get(0))
+}
+pub fn set_user_plan(username: &str, plan: Option<&str>) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "UPDATE users SET plan = ?1 WHERE username = ?2",
+        params![plan, username],
+    )?;
+    Ok(())
 }
 
 pub fn count_containers_by_username(id: &str) -> Result<i32> {
@@ -196,17 +515,26 @@ pub struct Container {
     pub cpu_shares: i64,
     pub cpu_cores: i64,
     pub port: i64,
+    pub image: Option<String>,
+    pub cmd: Option<Vec<String>>,
+    pub entrypoint: Option<Vec<String>>,
+    pub working_dir: Option<String>,
+    pub user: Option<String>,
+    pub labels: Option<HashMap<String, String>>,
+    pub disk: Option<i64>,
+    pub sleeping: bool,
+    pub health: Option<String>,
 }
 pub fn get_user_containers(username: &str) -> Result<Vec<Container>> {
     let conn = Connection::open("./dockify.db")?;
 
     let mut stmt = conn.prepare(
-        "SELECT id, name, memory, memory_swap, cpu_shares, cpu_cores, port FROM containers WHERE username = ?1"
+        "SELECT id, name, memory, memory_swap, cpu_shares, cpu_cores, port, image, cmd, entrypoint, working_dir, user, labels, disk, sleeping, health FROM containers WHERE username = ?1"
     )?;
 
     let mut rows = stmt.query(params![username])?;
     let mut containers: Vec<Container> = Vec::new();
-    if let Some(row) = rows.next()? {
+    while let Some(row) = rows.next()? {
         let container = Container {
             id: row.get(0)?,
             name: row.get(1)?,
@@ -215,13 +543,34 @@ pub fn get_user_containers(username: &str) -> Result<Vec<Container>> {
             cpu_shares: row.get(4)?,
             cpu_cores: row.get(5)?,
             port: row.get(6)?,
+            image: row.get(7)?,
+            cmd: from_json(row.get(8)?),
+            entrypoint: from_json(row.get(9)?),
+            working_dir: row.get(10)?,
+            user: row.get(11)?,
+            labels: from_json(row.get(12)?),
+            disk: row.get(13)?,
+            sleeping: row.get(14)?,
+            health: row.get(15)?,
         };
         containers.insert(containers.len(), container);
-    } else {
+    }
+    if containers.is_empty() {
         return Err(rusqlite::Error::QueryReturnedNoRows);
     }
     Ok(containers)
 }
+pub fn delete_container(name: &str, username: &str) -> Result<usize> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.execute(
+        "DELETE FROM schedules WHERE container = ?1 AND username = ?2",
+        params![name, username],
+    )?;
+    conn.execute(
+        "DELETE FROM containers WHERE name = ?1 AND username = ?2",
+        params![name, username],
+    )
+}
 pub fn get_user_credits(username: &str) -> Result<i64> {
     let conn = Connection::open("./dockify.db")?;
     let mut stmt = conn.prepare("SELECT credits FROM credits WHERE username = ?1")?;
@@ -246,3 +595,1480 @@ pub fn set_user_credits(username: &str, credits: i64) -> Result<()> {
     )?;
     Ok(())
 }
+pub fn set_secret(username: &str, name: &str, nonce: &str, value: &str) -> Result<()> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.execute(
+        "INSERT INTO secrets (username, name, nonce, value)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(username, name) DO UPDATE SET nonce = excluded.nonce, value = excluded.value",
+        params![username, name, nonce, value],
+    )?;
+    Ok(())
+}
+pub fn delete_secret(username: &str, name: &str) -> Result<usize> {
+    Connection::open("./dockify.db")?.execute(
+        "DELETE FROM secrets WHERE username = ?1 AND name = ?2",
+        params![username, name],
+    )
+}
+pub fn get_secret_names(username: &str) -> Result<Vec<String>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare("SELECT name FROM secrets WHERE username = ?1 ORDER BY name")?;
+    let names = stmt
+        .query_map(params![username], |row| row.get(0))?
+        .collect::<Result<Vec<String>>>()?;
+    Ok(names)
+}
+pub fn get_secret(username: &str, name: &str) -> Result<(String, String)> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt =
+        conn.prepare("SELECT nonce, value FROM secrets WHERE username = ?1 AND name = ?2")?;
+    stmt.query_row(params![username, name], |row| {
+        Ok((row.get(0)?, row.get(1)?))
+    })
+}
+pub fn insert_stack(name: &str, username: &str, network: &str, spec: &str) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "INSERT INTO stacks (name, username, network, spec) VALUES (?1, ?2, ?3, ?4)",
+        params![name, username, network, spec],
+    )?;
+    Ok(())
+}
+pub fn get_stack_spec(name: &str) -> Result<String> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare("SELECT spec FROM stacks WHERE name = ?1")?;
+    stmt.query_row(params![name], |row| row.get(0))
+}
+// Container names of a user's stack in the order they were started
+pub fn get_stack_containers(name: &str, username: &str) -> Result<Vec<String>> {
+    let conn = Connection::open("./dockify.db")?;
+    check_exists_for_user(&conn, name, username)?;
+    let mut stmt = conn
+        .prepare("SELECT name FROM containers WHERE stack = ?1 AND username = ?2 ORDER BY rowid")?;
+    let names = stmt
+        .query_map(params![name, username], |row| row.get(0))?
+        .collect::<Result<Vec<String>>>()?;
+    Ok(names)
+}
+fn check_exists_for_user(conn: &Connection, name: &str, username: &str) -> Result<()> {
+    conn.query_row(
+        "SELECT 1 FROM stacks WHERE name = ?1 AND username = ?2",
+        params![name, username],
+        |_| Ok(()),
+    )
+}
+pub fn delete_stack(name: &str) -> Result<()> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.execute(
+        "DELETE FROM schedules WHERE container IN (SELECT name FROM containers WHERE stack = ?1)",
+        params![name],
+    )?;
+    conn.execute("DELETE FROM containers WHERE stack = ?1", params![name])?;
+    conn.execute("DELETE FROM stacks WHERE name = ?1", params![name])?;
+    Ok(())
+}
+pub fn get_user_stacks(username: &str) -> Result<Vec<String>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare("SELECT name FROM stacks WHERE username = ?1")?;
+    let names = stmt
+        .query_map(params![username], |row| row.get(0))?
+        .collect::<Result<Vec<String>>>()?;
+    Ok(names)
+}
+// Containers that aren't part of a stack
+pub fn get_standalone_containers(username: &str) -> Result<Vec<String>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt =
+        conn.prepare("SELECT name FROM containers WHERE username = ?1 AND stack IS NULL")?;
+    let names = stmt
+        .query_map(params![username], |row| row.get(0))?
+        .collect::<Result<Vec<String>>>()?;
+    Ok(names)
+}
+pub fn get_user_network(username: &str) -> Result<String> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare("SELECT network FROM user_networks WHERE username = ?1")?;
+    stmt.query_row(params![username], |row| row.get(0))
+}
+pub fn set_user_network(username: &str, network: &str) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "INSERT INTO user_networks (username, network)
+         VALUES (?1, ?2)
+         ON CONFLICT(username) DO UPDATE SET network = excluded.network",
+        params![username, network],
+    )?;
+    Ok(())
+}
+pub fn delete_user_network(username: &str) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "DELETE FROM user_networks WHERE username = ?1",
+        params![username],
+    )?;
+    Ok(())
+}
+pub struct DiskQuota {
+    pub id: String,
+    pub name: String,
+    pub username: String,
+    pub disk: i64,
+    pub warned: bool,
+}
+pub fn get_disk_quotas() -> Result<Vec<DiskQuota>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare(
+        "SELECT id, name, username, disk, disk_warned FROM containers WHERE disk IS NOT NULL AND disk > 0",
+    )?;
+    let quotas = stmt
+        .query_map([], |row| {
+            Ok(DiskQuota {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                username: row.get(2)?,
+                disk: row.get(3)?,
+                warned: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<DiskQuota>>>()?;
+    Ok(quotas)
+}
+pub fn set_disk_warned(id: &str, warned: bool) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "UPDATE containers SET disk_warned = ?1 WHERE id = ?2",
+        params![warned, id],
+    )?;
+    Ok(())
+}
+pub fn get_all_container_owners() -> Result<Vec<(String, String)>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare("SELECT id, username FROM containers")?;
+    let owners = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<(String, String)>>>()?;
+    Ok(owners)
+}
+// Docker's counters reset when a container restarts, so only the growth since
+// the last sample is added to the period's usage
+pub fn record_network_usage(
+    id: &str,
+    username: &str,
+    period: &str,
+    rx: i64,
+    tx: i64,
+) -> Result<()> {
+    let conn = Connection::open("./dockify.db")?;
+    let last: Option<(i64, i64)> = conn
+        .query_row(
+            "SELECT last_rx, last_tx FROM network_counters WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .ok();
+    let (delta_rx, delta_tx) = match last {
+        Some((last_rx, last_tx)) => (
+            if rx >= last_rx { rx - last_rx } else { rx },
+            if tx >= last_tx { tx - last_tx } else { tx },
+        ),
+        None => (rx, tx),
+    };
+    conn.execute(
+        "INSERT INTO network_counters (id, last_rx, last_tx)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT(id) DO UPDATE SET last_rx = excluded.last_rx, last_tx = excluded.last_tx",
+        params![id, rx, tx],
+    )?;
+    conn.execute(
+        "INSERT INTO network_usage (id, username, period, rx, tx)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(id, period) DO UPDATE SET rx = rx + excluded.rx, tx = tx + excluded.tx",
+        params![id, username, period, delta_rx, delta_tx],
+    )?;
+    Ok(())
+}
+pub fn get_user_network_usage(username: &str, period: &str) -> Result<(i64, i64)> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.query_row(
+        "SELECT COALESCE(SUM(rx), 0), COALESCE(SUM(tx), 0) FROM network_usage WHERE username = ?1 AND period = ?2",
+        params![username, period],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+}
+#[derive(Serialize)]
+pub struct ContainerUsage {
+    pub name: String,
+    pub rx: i64,
+    pub tx: i64,
+}
+pub fn get_container_network_usage(username: &str, period: &str) -> Result<Vec<ContainerUsage>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare(
+        "SELECT COALESCE(containers.name, network_usage.id), network_usage.rx, network_usage.tx
+         FROM network_usage LEFT JOIN containers ON containers.id = network_usage.id
+         WHERE network_usage.username = ?1 AND network_usage.period = ?2",
+    )?;
+    let usage = stmt
+        .query_map(params![username, period], |row| {
+            Ok(ContainerUsage {
+                name: row.get(0)?,
+                rx: row.get(1)?,
+                tx: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<ContainerUsage>>>()?;
+    Ok(usage)
+}
+pub fn get_egress_billing(username: &str, period: &str) -> Result<(i64, bool)> {
+    let conn = Connection::open("./dockify.db")?;
+    Ok(conn
+        .query_row(
+            "SELECT charged_gb, throttled FROM egress_billing WHERE username = ?1 AND period = ?2",
+            params![username, period],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap_or((0, false)))
+}
+pub fn set_egress_billing(
+    username: &str,
+    period: &str,
+    charged_gb: i64,
+    throttled: bool,
+) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "INSERT INTO egress_billing (username, period, charged_gb, throttled)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(username, period) DO UPDATE SET charged_gb = excluded.charged_gb, throttled = excluded.throttled",
+        params![username, period, charged_gb, throttled],
+    )?;
+    Ok(())
+}
+pub fn is_egress_throttled(username: &str, period: &str) -> Result<bool> {
+    Ok(get_egress_billing(username, period)?.1)
+}
+pub fn get_throttled_users(period: &str) -> Result<Vec<String>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt =
+        conn.prepare("SELECT username FROM egress_billing WHERE period = ?1 AND throttled = 1")?;
+    let users = stmt
+        .query_map(params![period], |row| row.get(0))?
+        .collect::<Result<Vec<String>>>()?;
+    Ok(users)
+}
+pub fn get_container_name(id: &str) -> Result<String> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.query_row(
+        "SELECT name FROM containers WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    )
+}
+// Options the container was created with, so it can be created again the same way
+pub fn get_container_options(name: &str) -> Result<RuntimeOptions> {
+    Connection::open("./dockify.db")?.query_row(
+        "SELECT cmd, entrypoint, working_dir, user, labels FROM containers WHERE name = ?1",
+        params![name],
+        |row| {
+            Ok(RuntimeOptions {
+                cmd: from_json(row.get(0)?),
+                entrypoint: from_json(row.get(1)?),
+                working_dir: row.get(2)?,
+                user: row.get(3)?,
+                labels: from_json(row.get(4)?),
+                healthcheck: None,
+            })
+        },
+    )
+}
+pub fn get_container_stack(name: &str) -> Result<Option<String>> {
+    Connection::open("./dockify.db")?.query_row(
+        "SELECT stack FROM containers WHERE name = ?1",
+        params![name],
+        |row| row.get(0),
+    )
+}
+// Docker assigns a new ID when a container is recreated
+pub fn set_container_id(name: &str, id: &str) -> Result<()> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.execute(
+        "UPDATE network_counters SET id = ?1 WHERE id = (SELECT id FROM containers WHERE name = ?2)",
+        params![id, name],
+    )?;
+    conn.execute(
+        "UPDATE containers SET id = ?1 WHERE name = ?2",
+        params![id, name],
+    )?;
+    Ok(())
+}
+pub fn set_sleeping(name: &str, sleeping: bool) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "UPDATE containers SET sleeping = ?1 WHERE name = ?2",
+        params![sleeping, name],
+    )?;
+    Ok(())
+}
+// Owner, published port and sleep state of a container, used for request routing
+pub fn get_container_route(name: &str) -> Result<(String, u16, bool)> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.query_row(
+        "SELECT username, port, sleeping FROM containers WHERE name = ?1",
+        params![name],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )
+}
+#[derive(Serialize)]
+pub struct ContainerSchedule {
+    pub id: i64,
+    #[serde(skip)]
+    pub username: String,
+    pub container: String,
+    pub action: String,
+    pub cron: String,
+    pub last_run: Option<i64>,
+    pub next_run: Option<i64>,
+}
+fn schedule_from_row(row: &rusqlite::Row) -> Result<ContainerSchedule> {
+    Ok(ContainerSchedule {
+        id: row.get(0)?,
+        username: row.get(1)?,
+        container: row.get(2)?,
+        action: row.get(3)?,
+        cron: row.get(4)?,
+        last_run: row.get(5)?,
+        next_run: row.get(6)?,
+    })
+}
+pub fn insert_schedule(
+    username: &str,
+    container: &str,
+    action: &str,
+    cron: &str,
+    next_run: Option<i64>,
+) -> Result<i64> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.execute(
+        "INSERT INTO schedules (username, container, action, cron, next_run) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![username, container, action, cron, next_run],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+pub fn get_user_schedules(username: &str) -> Result<Vec<ContainerSchedule>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare(
+        "SELECT id, username, container, action, cron, last_run, next_run FROM schedules WHERE username = ?1 ORDER BY id",
+    )?;
+    let schedules = stmt
+        .query_map(params![username], schedule_from_row)?
+        .collect::<Result<Vec<ContainerSchedule>>>()?;
+    Ok(schedules)
+}
+pub fn get_due_schedules(now: i64) -> Result<Vec<ContainerSchedule>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare(
+        "SELECT id, username, container, action, cron, last_run, next_run FROM schedules WHERE next_run IS NOT NULL AND next_run <= ?1 ORDER BY next_run",
+    )?;
+    let schedules = stmt
+        .query_map(params![now], schedule_from_row)?
+        .collect::<Result<Vec<ContainerSchedule>>>()?;
+    Ok(schedules)
+}
+pub fn set_schedule_run(id: i64, last_run: i64, next_run: Option<i64>) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "UPDATE schedules SET last_run = ?1, next_run = ?2 WHERE id = ?3",
+        params![last_run, next_run, id],
+    )?;
+    Ok(())
+}
+pub fn delete_schedule(id: i64, username: &str) -> Result<usize> {
+    Connection::open("./dockify.db")?.execute(
+        "DELETE FROM schedules WHERE id = ?1 AND username = ?2",
+        params![id, username],
+    )
+}
+pub struct HealthStatus {
+    pub id: String,
+    pub name: String,
+    pub username: String,
+    pub health: Option<String>,
+    pub sleeping: bool,
+}
+pub fn get_container_health() -> Result<Vec<HealthStatus>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare("SELECT id, name, username, health, sleeping FROM containers")?;
+    let statuses = stmt
+        .query_map([], |row| {
+            Ok(HealthStatus {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                username: row.get(2)?,
+                health: row.get(3)?,
+                sleeping: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<HealthStatus>>>()?;
+    Ok(statuses)
+}
+pub fn set_container_health(name: &str, health: Option<&str>) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "UPDATE containers SET health = ?1 WHERE name = ?2",
+        params![health, name],
+    )?;
+    Ok(())
+}
+// Email notifications are on by default, webhooks are opt-in
+pub fn get_notification_settings(username: &str) -> Result<(bool, Option<String>)> {
+    let conn = Connection::open("./dockify.db")?;
+    match conn.query_row(
+        "SELECT email, webhook FROM notification_settings WHERE username = ?1",
+        params![username],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ) {
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok((true, None)),
+        result => result,
+    }
+}
+pub fn set_notification_settings(username: &str, email: bool, webhook: Option<&str>) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "INSERT INTO notification_settings (username, email, webhook) VALUES (?1, ?2, ?3)
+            ON CONFLICT(username) DO UPDATE SET email = excluded.email, webhook = excluded.webhook",
+        params![username, email, webhook],
+    )?;
+    Ok(())
+}
+pub fn get_container_owner(id: &str) -> Result<(String, String)> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.query_row(
+        "SELECT name, username FROM containers WHERE id = ?1",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+}
+#[derive(Serialize, Clone)]
+pub struct ContainerEvent {
+    pub id: i64,
+    #[serde(skip)]
+    pub username: String,
+    pub container: String,
+    pub action: String,
+    pub exit_code: Option<i64>,
+    pub time: i64,
+}
+pub fn insert_container_event(event: &ContainerEvent) -> Result<i64> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.execute(
+        "INSERT INTO container_events (username, container, action, exit_code, time) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![event.username, event.container, event.action, event.exit_code, event.time],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+// Newest first, `before` is an event id used for paging
+pub fn get_container_events(
+    username: &str,
+    before: Option<i64>,
+    limit: i64,
+) -> Result<Vec<ContainerEvent>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare(
+        "SELECT id, username, container, action, exit_code, time FROM container_events
+            WHERE username = ?1 AND id < ?2 ORDER BY id DESC LIMIT ?3",
+    )?;
+    let events = stmt
+        .query_map(
+            params![username, before.unwrap_or(i64::MAX), limit],
+            |row| {
+                Ok(ContainerEvent {
+                    id: row.get(0)?,
+                    username: row.get(1)?,
+                    container: row.get(2)?,
+                    action: row.get(3)?,
+                    exit_code: row.get(4)?,
+                    time: row.get(5)?,
+                })
+            },
+        )?
+        .collect::<Result<Vec<ContainerEvent>>>()?;
+    Ok(events)
+}
+pub fn prune_container_events(before: i64) -> Result<usize> {
+    Connection::open("./dockify.db")?.execute(
+        "DELETE FROM container_events WHERE time < ?1",
+        params![before],
+    )
+}
+// Replaces any earlier reset token so only the latest email works
+pub fn insert_password_reset(username: &str, token_hash: &str, expires: i64) -> Result<()> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.execute(
+        "DELETE FROM password_resets WHERE username = ?1 OR expires < ?2",
+        params![username, chrono::Utc::now().timestamp()],
+    )?;
+    conn.execute(
+        "INSERT INTO password_resets (token_hash, username, expires) VALUES (?1, ?2, ?3)",
+        params![token_hash, username, expires],
+    )?;
+    Ok(())
+}
+// Deletes the token and returns its user if it had not expired
+pub fn take_password_reset(token_hash: &str, now: i64) -> Result<Option<String>> {
+    let conn = Connection::open("./dockify.db")?;
+    let reset: Option<(String, i64)> = match conn.query_row(
+        "DELETE FROM password_resets WHERE token_hash = ?1 RETURNING username, expires",
+        params![token_hash],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ) {
+        Ok(reset) => Some(reset),
+        Err(rusqlite::Error::QueryReturnedNoRows) => None,
+        Err(err) => return Err(err),
+    };
+    Ok(reset
+        .filter(|(_, expires)| *expires >= now)
+        .map(|(username, _)| username))
+}
+// Sets a new password and logs the user out everywhere
+pub fn reset_password(username: &str, hash: &str) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "UPDATE users SET hash = ?1 WHERE username = ?2",
+        params![hash, username],
+    )?;
+    revoke_user_sessions(username)?;
+    Ok(())
+}
+pub fn insert_session(
+    id: &str,
+    username: &str,
+    refresh_hash: &str,
+    created: i64,
+    expires: i64,
+) -> Result<()> {
+    let conn = Connection::open("./dockify.db")?;
+    // Dead sessions are cleaned up whenever the user logs in
+    conn.execute(
+        "DELETE FROM sessions WHERE username = ?1 AND (revoked = 1 OR expires < ?2)",
+        params![username, created],
+    )?;
+    conn.execute(
+        "INSERT INTO sessions (id, username, refresh_hash, created, expires) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![id, username, refresh_hash, created, expires],
+    )?;
+    Ok(())
+}
+pub fn is_session_active(id: &str, username: &str, now: i64) -> Result<bool> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sessions WHERE id = ?1 AND username = ?2 AND revoked = 0 AND expires >= ?3)",
+        params![id, username, now],
+        |row| row.get(0),
+    )
+}
+pub enum Refresh {
+    // Session id and username of the rotated session
+    Rotated(String, String),
+    // An already rotated token was presented again, the session was revoked
+    Reused,
+    Invalid,
+}
+pub fn rotate_session(refresh_hash: &str, new_hash: &str, now: i64) -> Result<Refresh> {
+    let conn = Connection::open("./dockify.db")?;
+    let rotated = conn.query_row(
+        "UPDATE sessions SET previous_hash = refresh_hash, refresh_hash = ?1
+            WHERE refresh_hash = ?2 AND revoked = 0 AND expires >= ?3 RETURNING id, username",
+        params![new_hash, refresh_hash, now],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    );
+    match rotated {
+        Ok((id, username)) => Ok(Refresh::Rotated(id, username)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            let reused = conn.execute(
+                "UPDATE sessions SET revoked = 1 WHERE previous_hash = ?1",
+                params![refresh_hash],
+            )?;
+            Ok(if reused > 0 {
+                Refresh::Reused
+            } else {
+                Refresh::Invalid
+            })
+        }
+        Err(err) => Err(err),
+    }
+}
+// Sets a new password and logs out every other session
+pub fn change_password(username: &str, hash: &str, keep_session: Option<&str>) -> Result<()> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.execute(
+        "UPDATE users SET hash = ?1 WHERE username = ?2",
+        params![hash, username],
+    )?;
+    conn.execute(
+        "UPDATE sessions SET revoked = 1 WHERE username = ?1 AND id IS NOT ?2",
+        params![username, keep_session],
+    )?;
+    Ok(())
+}
+pub fn revoke_session(id: &str, username: &str) -> Result<usize> {
+    Connection::open("./dockify.db")?.execute(
+        "UPDATE sessions SET revoked = 1 WHERE id = ?1 AND username = ?2",
+        params![id, username],
+    )
+}
+pub fn revoke_user_sessions(username: &str) -> Result<usize> {
+    Connection::open("./dockify.db")?.execute(
+        "UPDATE sessions SET revoked = 1 WHERE username = ?1",
+        params![username],
+    )
+}
+#[derive(Serialize)]
+pub struct ActiveSession {
+    pub created: i64,
+    pub expires: i64,
+    // Whether this is the session making the request
+    pub current: bool,
+}
+pub fn get_active_sessions(
+    username: &str,
+    current: Option<&str>,
+    now: i64,
+) -> Result<Vec<ActiveSession>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare(
+        "SELECT id, created, expires FROM sessions WHERE username = ?1 AND revoked = 0 AND expires >= ?2 ORDER BY created DESC",
+    )?;
+    let sessions = stmt
+        .query_map(params![username, now], |row| {
+            let id: String = row.get(0)?;
+            Ok(ActiveSession {
+                created: row.get(1)?,
+                expires: row.get(2)?,
+                current: current == Some(id.as_str()),
+            })
+        })?
+        .collect::<Result<Vec<ActiveSession>>>()?;
+    Ok(sessions)
+}
+pub struct Mfa {
+    pub nonce: String,
+    pub secret: String,
+    pub enabled: bool,
+    pub last_step: Option<i64>,
+}
+pub fn get_mfa(username: &str) -> Result<Option<Mfa>> {
+    let conn = Connection::open("./dockify.db")?;
+    match conn.query_row(
+        "SELECT nonce, secret, enabled, last_step FROM mfa WHERE username = ?1",
+        params![username],
+        |row| {
+            Ok(Mfa {
+                nonce: row.get(0)?,
+                secret: row.get(1)?,
+                enabled: row.get(2)?,
+                last_step: row.get(3)?,
+            })
+        },
+    ) {
+        Ok(mfa) => Ok(Some(mfa)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+pub fn is_mfa_enabled(username: &str) -> Result<bool> {
+    Ok(get_mfa(username)?.is_some_and(|mfa| mfa.enabled))
+}
+// A pending secret only becomes active once a code for it is confirmed
+pub fn set_pending_mfa(username: &str, nonce: &str, secret: &str) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "INSERT INTO mfa (username, nonce, secret, enabled) VALUES (?1, ?2, ?3, 0)
+            ON CONFLICT(username) DO UPDATE SET nonce = excluded.nonce, secret = excluded.secret, enabled = 0, last_step = NULL",
+        params![username, nonce, secret],
+    )?;
+    Ok(())
+}
+pub fn enable_mfa(username: &str) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "UPDATE mfa SET enabled = 1 WHERE username = ?1",
+        params![username],
+    )?;
+    Ok(())
+}
+pub fn set_mfa_step(username: &str, step: i64) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "UPDATE mfa SET last_step = ?1 WHERE username = ?2",
+        params![step, username],
+    )?;
+    Ok(())
+}
+pub fn delete_mfa(username: &str) -> Result<()> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.execute("DELETE FROM mfa WHERE username = ?1", params![username])?;
+    conn.execute(
+        "DELETE FROM recovery_codes WHERE username = ?1",
+        params![username],
+    )?;
+    Ok(())
+}
+pub fn set_recovery_codes(username: &str, hashes: &[String]) -> Result<()> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.execute(
+        "DELETE FROM recovery_codes WHERE username = ?1",
+        params![username],
+    )?;
+    for hash in hashes {
+        conn.execute(
+            "INSERT INTO recovery_codes (username, hash) VALUES (?1, ?2)",
+            params![username, hash],
+        )?;
+    }
+    Ok(())
+}
+pub fn get_recovery_codes(username: &str) -> Result<Vec<(i64, String)>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare("SELECT id, hash FROM recovery_codes WHERE username = ?1")?;
+    let codes = stmt
+        .query_map(params![username], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<(i64, String)>>>()?;
+    Ok(codes)
+}
+pub fn delete_recovery_code(id: i64) -> Result<()> {
+    Connection::open("./dockify.db")?
+        .execute("DELETE FROM recovery_codes WHERE id = ?1", params![id])?;
+    Ok(())
+}
+#[derive(Serialize)]
+pub struct ApiToken {
+    pub id: i64,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created: i64,
+    pub expires: Option<i64>,
+    pub last_used: Option<i64>,
+}
+pub fn insert_api_token(
+    username: &str,
+    name: &str,
+    token_hash: &str,
+    scopes: &[String],
+    expires: Option<i64>,
+) -> Result<i64> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.execute(
+        "INSERT INTO api_tokens (username, name, token_hash, scopes, created, expires) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            username,
+            name,
+            token_hash,
+            to_json(&Some(scopes)),
+            chrono::Utc::now().timestamp(),
+            expires
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+pub fn get_api_tokens(username: &str) -> Result<Vec<ApiToken>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare(
+        "SELECT id, name, scopes, created, expires, last_used FROM api_tokens WHERE username = ?1 ORDER BY id",
+    )?;
+    let tokens = stmt
+        .query_map(params![username], |row| {
+            Ok(ApiToken {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                scopes: from_json(row.get(2)?).unwrap_or_default(),
+                created: row.get(3)?,
+                expires: row.get(4)?,
+                last_used: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<ApiToken>>>()?;
+    Ok(tokens)
+}
+pub fn delete_api_token(id: i64, username: &str) -> Result<usize> {
+    Connection::open("./dockify.db")?.execute(
+        "DELETE FROM api_tokens WHERE id = ?1 AND username = ?2",
+        params![id, username],
+    )
+}
+// Returns the owner of an unexpired token and records its use
+pub fn use_api_token(token_hash: &str, now: i64) -> Result<Option<String>> {
+    let conn = Connection::open("./dockify.db")?;
+    match conn.query_row(
+        "UPDATE api_tokens SET last_used = ?1
+            WHERE token_hash = ?2 AND (expires IS NULL OR expires >= ?1) RETURNING username",
+        params![now, token_hash],
+        |row| row.get(0),
+    ) {
+        Ok(username) => Ok(Some(username)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+pub fn get_api_token_scopes(token_hash: &str) -> Result<Option<Vec<String>>> {
+    let conn = Connection::open("./dockify.db")?;
+    match conn.query_row(
+        "SELECT scopes FROM api_tokens WHERE token_hash = ?1",
+        params![token_hash],
+        |row| row.get(0),
+    ) {
+        Ok(scopes) => Ok(from_json(Some(scopes))),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+pub fn insert_oidc_state(state: &str, nonce: &str, verifier: &str, created: i64) -> Result<()> {
+    let conn = Connection::open("./dockify.db")?;
+    // Abandoned logins are cleaned up as new ones start
+    conn.execute(
+        "DELETE FROM oidc_states WHERE created < ?1",
+        params![created - 60 * 60],
+    )?;
+    conn.execute(
+        "INSERT INTO oidc_states (state, nonce, verifier, created) VALUES (?1, ?2, ?3, ?4)",
+        params![state, nonce, verifier, created],
+    )?;
+    Ok(())
+}
+// Deletes the state and returns its nonce and PKCE verifier if created after `since`
+pub fn take_oidc_state(state: &str, since: i64) -> Result<Option<(String, String)>> {
+    let conn = Connection::open("./dockify.db")?;
+    match conn.query_row(
+        "DELETE FROM oidc_states WHERE state = ?1 RETURNING nonce, verifier, created",
+        params![state],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, i64>(2)?)),
+    ) {
+        Ok((nonce, verifier, created)) if created >= since => Ok(Some((nonce, verifier))),
+        Ok(_) | Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+pub fn get_oidc_identity(issuer: &str, subject: &str) -> Result<Option<String>> {
+    let conn = Connection::open("./dockify.db")?;
+    match conn.query_row(
+        "SELECT username FROM oidc_identities WHERE issuer = ?1 AND subject = ?2",
+        params![issuer, subject],
+        |row| row.get(0),
+    ) {
+        Ok(username) => Ok(Some(username)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+pub fn insert_oidc_identity(issuer: &str, subject: &str, username: &str) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "INSERT INTO oidc_identities (issuer, subject, username) VALUES (?1, ?2, ?3)",
+        params![issuer, subject, username],
+    )?;
+    Ok(())
+}
+#[derive(Serialize)]
+pub struct UserDetails {
+    pub username: String,
+    pub dusername: String,
+    pub email: String,
+    pub verified: bool,
+    pub plan: Option<String>,
+    pub credits: i64,
+    pub created: Option<i64>,
+}
+pub fn get_user_details(identifier: &str) -> Result<Option<UserDetails>> {
+    let conn = Connection::open("./dockify.db")?;
+    match conn.query_row(
+        "SELECT users.username, dusername, email, verified, plan, COALESCE(credits.credits, 0), created
+            FROM users LEFT JOIN credits ON credits.username = users.username
+            WHERE users.username = ?1 OR email = ?1",
+        params![identifier],
+        |row| {
+            Ok(UserDetails {
+                username: row.get(0)?,
+                dusername: row.get(1)?,
+                email: row.get(2)?,
+                verified: row.get::<_, i64>(3)? != 0,
+                plan: row.get(4)?,
+                credits: row.get(5)?,
+                created: row.get(6)?,
+            })
+        },
+    ) {
+        Ok(user) => Ok(Some(user)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+pub fn get_user_roles(username: &str) -> Result<Vec<String>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare("SELECT role FROM user_roles WHERE username = ?1")?;
+    let roles = stmt
+        .query_map(params![username], |row| row.get(0))?
+        .collect::<Result<Vec<String>>>()?;
+    Ok(roles)
+}
+// Returns false if the user already had the role
+pub fn grant_role(username: &str, role: &str, granted_by: &str, granted: i64) -> Result<bool> {
+    let changed = Connection::open("./dockify.db")?.execute(
+        "INSERT OR IGNORE INTO user_roles (username, role, granted_by, granted) VALUES (?1, ?2, ?3, ?4)",
+        params![username, role, granted_by, granted],
+    )?;
+    Ok(changed > 0)
+}
+pub fn count_role(role: &str) -> Result<i64> {
+    Connection::open("./dockify.db")?.query_row(
+        "SELECT COUNT(*) FROM user_roles WHERE role = ?1",
+        params![role],
+        |row| row.get(0),
+    )
+}
+pub fn revoke_role(username: &str, role: &str) -> Result<bool> {
+    let changed = Connection::open("./dockify.db")?.execute(
+        "DELETE FROM user_roles WHERE username = ?1 AND role = ?2",
+        params![username, role],
+    )?;
+    Ok(changed > 0)
+}
+#[derive(Serialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub actor: String,
+    pub action: String,
+    pub target: String,
+    pub detail: Option<String>,
+    pub time: i64,
+}
+pub fn insert_audit_entry(
+    actor: &str,
+    action: &str,
+    target: &str,
+    detail: Option<&str>,
+    time: i64,
+) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "INSERT INTO audit_log (actor, action, target, detail, time) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![actor, action, target, detail, time],
+    )?;
+    Ok(())
+}
+// Newest first, `before` is an entry id used for paging
+pub fn get_audit_log(
+    target: Option<&str>,
+    before: Option<i64>,
+    limit: i64,
+) -> Result<Vec<AuditEntry>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare(
+        "SELECT id, actor, action, target, detail, time FROM audit_log
+            WHERE (?1 IS NULL OR target = ?1) AND id < ?2 ORDER BY id DESC LIMIT ?3",
+    )?;
+    let entries = stmt
+        .query_map(params![target, before.unwrap_or(i64::MAX), limit], |row| {
+            Ok(AuditEntry {
+                id: row.get(0)?,
+                actor: row.get(1)?,
+                action: row.get(2)?,
+                target: row.get(3)?,
+                detail: row.get(4)?,
+                time: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<AuditEntry>>>()?;
+    Ok(entries)
+}
+// Creates the org with `owner` as its first member, returns false if the slug or its
+// account name is already taken
+pub fn insert_org(
+    slug: &str,
+    account: &str,
+    name: &str,
+    owner: &str,
+    created: i64,
+) -> Result<bool> {
+    let mut conn = Connection::open("./dockify.db")?;
+    let tx = conn.transaction()?;
+    let account_taken: bool = tx.query_row(
+        "SELECT EXISTS(SELECT 1 FROM users WHERE username = ?1)",
+        params![account],
+        |row| row.get(0),
+    )?;
+    if account_taken {
+        return Ok(false);
+    }
+    if tx.execute(
+        "INSERT OR IGNORE INTO orgs (slug, name, created) VALUES (?1, ?2, ?3)",
+        params![slug, name, created],
+    )? == 0
+    {
+        return Ok(false);
+    }
+    tx.execute(
+        "INSERT INTO org_members (org, username, role, joined) VALUES (?1, ?2, 'owner', ?3)",
+        params![slug, owner, created],
+    )?;
+    tx.commit()?;
+    Ok(true)
+}
+// Removes the org and its account, any remaining credits go back to `refund_to`
+pub fn delete_org(slug: &str, account: &str, refund_to: &str) -> Result<()> {
+    let mut conn = Connection::open("./dockify.db")?;
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT INTO credits (username, credits)
+         SELECT ?2, credits FROM credits WHERE username = ?1 AND credits > 0
+         ON CONFLICT(username) DO UPDATE SET credits = credits + excluded.credits",
+        params![account, refund_to],
+    )?;
+    for stmt in [
+        "DELETE FROM org_members WHERE org = ?1",
+        "DELETE FROM org_invites WHERE org = ?1",
+        "DELETE FROM orgs WHERE slug = ?1",
+    ] {
+        tx.execute(stmt, params![slug])?;
+    }
+    for stmt in [
+        "DELETE FROM credits WHERE username = ?1",
+        "DELETE FROM secrets WHERE username = ?1",
+        "DELETE FROM notification_settings WHERE username = ?1",
+    ] {
+        tx.execute(stmt, params![account])?;
+    }
+    tx.commit()
+}
+pub fn get_org_role(slug: &str, username: &str) -> Result<Option<String>> {
+    let conn = Connection::open("./dockify.db")?;
+    match conn.query_row(
+        "SELECT role FROM org_members WHERE org = ?1 AND username = ?2",
+        params![slug, username],
+        |row| row.get(0),
+    ) {
+        Ok(role) => Ok(Some(role)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+// The longest standing owner, who receives the org's notifications
+pub fn get_org_owner(slug: &str) -> Result<Option<String>> {
+    let conn = Connection::open("./dockify.db")?;
+    match conn.query_row(
+        "SELECT username FROM org_members WHERE org = ?1 AND role = 'owner' ORDER BY joined LIMIT 1",
+        params![slug],
+        |row| row.get(0),
+    ) {
+        Ok(username) => Ok(Some(username)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+pub fn count_org_owners(slug: &str) -> Result<i64> {
+    Connection::open("./dockify.db")?.query_row(
+        "SELECT COUNT(*) FROM org_members WHERE org = ?1 AND role = 'owner'",
+        params![slug],
+        |row| row.get(0),
+    )
+}
+#[derive(Serialize)]
+pub struct OrgMember {
+    pub username: String,
+    pub role: String,
+    pub joined: i64,
+}
+pub fn get_org_members(slug: &str) -> Result<Vec<OrgMember>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn
+        .prepare("SELECT username, role, joined FROM org_members WHERE org = ?1 ORDER BY joined")?;
+    let members = stmt
+        .query_map(params![slug], |row| {
+            Ok(OrgMember {
+                username: row.get(0)?,
+                role: row.get(1)?,
+                joined: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<OrgMember>>>()?;
+    Ok(members)
+}
+#[derive(Serialize)]
+pub struct OrgMembership {
+    pub org: String,
+    pub name: String,
+    pub role: String,
+}
+pub fn get_user_orgs(username: &str) -> Result<Vec<OrgMembership>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare(
+        "SELECT orgs.slug, orgs.name, org_members.role FROM org_members
+            JOIN orgs ON orgs.slug = org_members.org WHERE org_members.username = ?1",
+    )?;
+    let orgs = stmt
+        .query_map(params![username], |row| {
+            Ok(OrgMembership {
+                org: row.get(0)?,
+                name: row.get(1)?,
+                role: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<OrgMembership>>>()?;
+    Ok(orgs)
+}
+// Returns false if the user is already a member
+pub fn add_org_member(slug: &str, username: &str, role: &str, joined: i64) -> Result<bool> {
+    let changed = Connection::open("./dockify.db")?.execute(
+        "INSERT OR IGNORE INTO org_members (org, username, role, joined) VALUES (?1, ?2, ?3, ?4)",
+        params![slug, username, role, joined],
+    )?;
+    Ok(changed > 0)
+}
+pub fn set_org_member_role(slug: &str, username: &str, role: &str) -> Result<bool> {
+    let changed = Connection::open("./dockify.db")?.execute(
+        "UPDATE org_members SET role = ?3 WHERE org = ?1 AND username = ?2",
+        params![slug, username, role],
+    )?;
+    Ok(changed > 0)
+}
+pub fn remove_org_member(slug: &str, username: &str) -> Result<bool> {
+    let changed = Connection::open("./dockify.db")?.execute(
+        "DELETE FROM org_members WHERE org = ?1 AND username = ?2",
+        params![slug, username],
+    )?;
+    Ok(changed > 0)
+}
+pub fn insert_org_invite(
+    token_hash: &str,
+    slug: &str,
+    email: &str,
+    role: &str,
+    invited_by: &str,
+    expires: i64,
+) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "INSERT INTO org_invites (token_hash, org, email, role, invited_by, expires) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![token_hash, slug, email, role, invited_by, expires],
+    )?;
+    Ok(())
+}
+// Deletes the invite if it was sent to `email` and returns its org and role if it
+// hasn't expired
+pub fn take_org_invite(
+    token_hash: &str,
+    email: &str,
+    now: i64,
+) -> Result<Option<(String, String)>> {
+    let conn = Connection::open("./dockify.db")?;
+    match conn.query_row(
+        "DELETE FROM org_invites WHERE token_hash = ?1 AND email = ?2 RETURNING org, role, expires",
+        params![token_hash, email],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, i64>(2)?)),
+    ) {
+        Ok((org, role, expires)) if expires > now => Ok(Some((org, role))),
+        Ok(_) | Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+// Moves credits between accounts, returns false if `from` doesn't have enough
+pub fn transfer_credits(from: &str, to: &str, credits: i64) -> Result<bool> {
+    let mut conn = Connection::open("./dockify.db")?;
+    let tx = conn.transaction()?;
+    if tx.execute(
+        "UPDATE credits SET credits = credits - ?2 WHERE username = ?1 AND credits >= ?2",
+        params![from, credits],
+    )? == 0
+    {
+        return Ok(false);
+    }
+    tx.execute(
+        "INSERT INTO credits (username, credits) VALUES (?1, ?2)
+         ON CONFLICT(username) DO UPDATE SET credits = credits + excluded.credits",
+        params![to, credits],
+    )?;
+    tx.commit()?;
+    Ok(true)
+}
+// Runtime settings stored as JSON, None when never set
+pub fn get_setting<T: DeserializeOwned>(key: &str) -> Result<Option<T>> {
+    let conn = Connection::open("./dockify.db")?;
+    match conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![key],
+        |row| row.get(0),
+    ) {
+        Ok(value) => Ok(from_json(Some(value))),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+pub fn set_setting<T: Serialize>(key: &str, value: &T) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, to_json(&Some(value))],
+    )?;
+    Ok(())
+}
+#[derive(Serialize)]
+pub struct SignupInvite {
+    pub id: i64,
+    pub created_by: String,
+    pub max_uses: i64,
+    pub uses: i64,
+    pub expires: Option<i64>,
+    pub created: i64,
+}
+pub fn insert_signup_invite(
+    code_hash: &str,
+    created_by: &str,
+    max_uses: i64,
+    expires: Option<i64>,
+    created: i64,
+) -> Result<i64> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.execute(
+        "INSERT INTO signup_invites (code_hash, created_by, max_uses, expires, created) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![code_hash, created_by, max_uses, expires, created],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+pub fn get_signup_invites() -> Result<Vec<SignupInvite>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare(
+        "SELECT id, created_by, max_uses, uses, expires, created FROM signup_invites ORDER BY id DESC",
+    )?;
+    let invites = stmt
+        .query_map([], |row| {
+            Ok(SignupInvite {
+                id: row.get(0)?,
+                created_by: row.get(1)?,
+                max_uses: row.get(2)?,
+                uses: row.get(3)?,
+                expires: row.get(4)?,
+                created: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<SignupInvite>>>()?;
+    Ok(invites)
+}
+pub fn delete_signup_invite(id: i64) -> Result<bool> {
+    let changed = Connection::open("./dockify.db")?
+        .execute("DELETE FROM signup_invites WHERE id = ?1", params![id])?;
+    Ok(changed > 0)
+}
+// Uses up one redemption of the code, returns false if it's unknown, used up or expired
+// Redeems the invite, if any, and inserts the user in one transaction so a failed
+// signup doesn't use up the invite. Returns false if the invite isn't valid.
+pub fn insert_user_with_invite(
+    email: &str,
+    username: &str,
+    hash: &str,
+    invite_hash: Option<&str>,
+) -> Result<bool> {
+    let mut conn = Connection::open("./dockify.db")?;
+    let tx = conn.transaction()?;
+    let now = chrono::Utc::now().timestamp();
+    if let Some(invite_hash) = invite_hash {
+        let changed = tx.execute(
+            "UPDATE signup_invites SET uses = uses + 1
+                WHERE code_hash = ?1 AND uses < max_uses AND (expires IS NULL OR expires > ?2)",
+            params![invite_hash, now],
+        )?;
+        if changed == 0 {
+            return Ok(false);
+        }
+    }
+    tx.execute(
+        "INSERT INTO users (email, username, hash, verified, dusername, created) VALUES (?1, ?2, ?3, 0, ?4, ?5)",
+        params![email.to_lowercase(), username.to_lowercase(), hash, username, now],
+    )?;
+    tx.commit()?;
+    Ok(true)
+}
+// Failed login counter for a "user:" or "ip:" key, returns (failures, locked_until, last_failure)
+pub fn get_login_failures(key: &str) -> Result<Option<(i64, i64, i64)>> {
+    let conn = Connection::open("./dockify.db")?;
+    match conn.query_row(
+        "SELECT failures, locked_until, last_failure FROM login_failures WHERE key = ?1",
+        params![key],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    ) {
+        Ok(row) => Ok(Some(row)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+pub fn set_login_failures(key: &str, failures: i64, locked_until: i64, now: i64) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "INSERT INTO login_failures (key, failures, locked_until, last_failure) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(key) DO UPDATE SET failures = excluded.failures,
+         locked_until = excluded.locked_until, last_failure = excluded.last_failure",
+        params![key, failures, locked_until, now],
+    )?;
+    Ok(())
+}
+pub fn clear_login_failures(key: &str) -> Result<bool> {
+    let deleted = Connection::open("./dockify.db")?
+        .execute("DELETE FROM login_failures WHERE key = ?1", params![key])?;
+    Ok(deleted > 0)
+}
+pub fn purge_login_failures(before: i64) -> Result<usize> {
+    Connection::open("./dockify.db")?.execute(
+        "DELETE FROM login_failures WHERE last_failure < ?1",
+        params![before],
+    )
+}
+#[derive(Serialize)]
+pub struct LoginEvent {
+    pub ip: String,
+    pub user_agent: Option<String>,
+    pub method: String,
+    pub success: bool,
+    pub created: i64,
+}
+pub fn insert_login_event(username: &str, event: &LoginEvent) -> Result<()> {
+    Connection::open("./dockify.db")?.execute(
+        "INSERT INTO ip_logs (username, ip, user_agent, method, success, created) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            username,
+            event.ip,
+            event.user_agent,
+            event.method,
+            event.success,
+            event.created
+        ],
+    )?;
+    Ok(())
+}
+pub fn get_login_events(username: &str, limit: i64) -> Result<Vec<LoginEvent>> {
+    let conn = Connection::open("./dockify.db")?;
+    let mut stmt = conn.prepare(
+        "SELECT ip, user_agent, method, success, created FROM ip_logs WHERE username = ?1 ORDER BY id DESC LIMIT ?2",
+    )?;
+    let events = stmt
+        .query_map(params![username, limit], |row| {
+            Ok(LoginEvent {
+                ip: row.get(0)?,
+                user_agent: row.get(1)?,
+                method: row.get(2)?,
+                success: row.get(3)?,
+                created: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<LoginEvent>>>()?;
+    Ok(events)
+}
+// Returns (any successful login, a successful login from this address)
+pub fn get_known_login(username: &str, ip: &str) -> Result<(bool, bool)> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM ip_logs WHERE username = ?1 AND success = 1),
+         EXISTS(SELECT 1 FROM ip_logs WHERE username = ?1 AND ip = ?2 AND success = 1)",
+        params![username, ip],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+}
+pub fn purge_login_events(before: i64) -> Result<usize> {
+    Connection::open("./dockify.db")?
+        .execute("DELETE FROM ip_logs WHERE created < ?1", params![before])
+}
+// Returns false if another user already has the name, ignoring case
+pub fn set_display_name(username: &str, dusername: &str) -> Result<bool> {
+    let conn = Connection::open("./dockify.db")?;
+    let taken: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM users WHERE (lower(dusername) = lower(?1) OR username = lower(?1)) AND username != ?2)",
+        params![dusername, username],
+        |row| row.get(0),
+    )?;
+    if taken {
+        return Ok(false);
+    }
+    conn.execute(
+        "UPDATE users SET dusername = ?1 WHERE username = ?2",
+        params![dusername, username],
+    )?;
+    Ok(true)
+}
+// Replaces any earlier pending change so only the latest email works
+pub fn insert_email_change(
+    username: &str,
+    email: &str,
+    token_hash: &str,
+    expires: i64,
+) -> Result<()> {
+    let conn = Connection::open("./dockify.db")?;
+    conn.execute(
+        "DELETE FROM email_changes WHERE username = ?1 OR expires < ?2",
+        params![username, chrono::Utc::now().timestamp()],
+    )?;
+    conn.execute(
+        "INSERT INTO email_changes (token_hash, username, email, expires) VALUES (?1, ?2, ?3, ?4)",
+        params![token_hash, username, email, expires],
+    )?;
+    Ok(())
+}
+// Deletes the token and returns its user and new email if it had not expired
+pub fn take_email_change(token_hash: &str, now: i64) -> Result<Option<(String, String)>> {
+    let conn = Connection::open("./dockify.db")?;
+    match conn.query_row(
+        "DELETE FROM email_changes WHERE token_hash = ?1 RETURNING username, email, expires",
+        params![token_hash],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, i64>(2)?)),
+    ) {
+        Ok((username, email, expires)) if expires >= now => Ok(Some((username, email))),
+        Ok(_) | Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+// Returns false if the email was taken since the change was requested
+pub fn set_email(username: &str, email: &str) -> Result<bool> {
+    match Connection::open("./dockify.db")?.execute(
+        "UPDATE users SET email = ?1 WHERE username = ?2",
+        params![email, username],
+    ) {
+        Ok(_) => Ok(true),
+        Err(rusqlite::Error::SqliteFailure(err, _))
+            if err.code == rusqlite::ErrorCode::ConstraintViolation =>
+        {
+            Ok(false)
+        }
+        Err(err) => Err(err),
+    }
+}
+// Removes everything stored for the user. Audit entries and signup invites are
+// kept for the admins but no longer name them.
+pub fn delete_account(username: &str, email: &str) -> Result<()> {
+    let mut conn = Connection::open("./dockify.db")?;
+    let tx = conn.transaction()?;
+    tx.execute(
+        "DELETE FROM network_counters WHERE id IN (SELECT id FROM containers WHERE username = ?1)",
+        params![username],
+    )?;
+    for table in [
+        "users",
+        "sessions",
+        "api_tokens",
+        "oidc_identities",
+        "user_roles",
+        "org_members",
+        "mfa",
+        "recovery_codes",
+        "password_resets",
+        "verification_codes",
+        "email_changes",
+        "credits",
+        "secrets",
+        "schedules",
+        "notification_settings",
+        "container_events",
+        "containers",
+        "stacks",
+        "user_networks",
+        "network_usage",
+        "egress_billing",
+        "ip_logs",
+    ] {
+        tx.execute(
+            &format!("DELETE FROM {} WHERE username = ?1", table),
+            params![username],
+        )?;
+    }
+    tx.execute(
+        "DELETE FROM login_failures WHERE key = 'user:' || ?1",
+        params![username],
+    )?;
+    tx.execute("DELETE FROM org_invites WHERE email = ?1", params![email])?;
+    for stmt in [
+        "UPDATE audit_log SET actor = 'deleted user' WHERE actor = ?1",
+        "UPDATE audit_log SET target = 'deleted user' WHERE target = ?1",
+        "UPDATE signup_invites SET created_by = 'deleted user' WHERE created_by = ?1",
+    ] {
+        tx.execute(stmt, params![username])?;
+    }
+    tx.commit()
+}
@@ -0,0 +1,103 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::{collections::HashMap, error::Error, time::Duration};
+
+use bollard::{system::EventsOptions, Docker};
+use chrono::Utc;
+use dotenvy::var;
+use futures_util::StreamExt;
+use once_cell::sync::Lazy;
+use tokio::sync::broadcast;
+
+use crate::utils::db::{self, ContainerEvent};
+
+// Actions worth keeping, health_status actions carry the status after a colon
+const ACTIONS: [&str; 9] = [
+    "start",
+    "stop",
+    "die",
+    "kill",
+    "oom",
+    "restart",
+    "pause",
+    "unpause",
+    "health_status",
+];
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+static RETENTION_DAYS: Lazy<i64> = Lazy::new(|| {
+    var("EVENT_RETENTION_DAYS")
+        .ok()
+        .and_then(|days| days.parse().ok())
+        .unwrap_or(30)
+});
+// Live events for WebSocket subscribers, slow receivers skip what they missed
+static LIVE: Lazy<broadcast::Sender<ContainerEvent>> = Lazy::new(|| broadcast::channel(256).0);
+
+pub fn subscribe() -> broadcast::Receiver<ContainerEvent> {
+    LIVE.subscribe()
+}
+
+pub async fn run_event_watcher() {
+    let mut prune = tokio::time::interval(PRUNE_INTERVAL);
+    loop {
+        if let Err(err) = watch_events(&mut prune).await {
+            eprintln!("Error while watching Docker events: {}", err);
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn watch_events(prune: &mut tokio::time::Interval) -> Result<(), Box<dyn Error>> {
+    let docker = Docker::connect_with_local_defaults()?;
+    let mut events = docker.events(Some(EventsOptions::<String> {
+        filters: HashMap::from([("type".to_string(), vec!["container".to_string()])]),
+        ..Default::default()
+    }));
+    loop {
+        tokio::select! {
+            _ = prune.tick() => {
+                let cutoff = Utc::now().timestamp() - *RETENTION_DAYS * 24 * 60 * 60;
+                db::prune_container_events(cutoff)?;
+            }
+            event = events.next() => {
+                let event = match event {
+                    Some(event) => event?,
+                    None => return Err("Docker event stream ended".into()),
+                };
+                let (Some(action), Some(actor)) = (event.action, event.actor) else {
+                    continue;
+                };
+                if !ACTIONS.contains(&action.split(':').next().unwrap_or("")) {
+                    continue;
+                }
+                let Some(id) = actor.id else { continue };
+                // Containers not created through Dockify are ignored
+                let (name, username) = match db::get_container_owner(&id) {
+                    Ok(owner) => owner,
+                    Err(rusqlite::Error::QueryReturnedNoRows) => continue,
+                    Err(err) => return Err(err.into()),
+                };
+                let exit_code = actor
+                    .attributes
+                    .and_then(|attributes| attributes.get("exitCode").cloned())
+                    .and_then(|code| code.parse().ok());
+                let mut event = ContainerEvent {
+                    id: 0,
+                    username,
+                    container: name,
+                    action: action.replace(": ", ":"),
+                    exit_code,
+                    time: event.time.unwrap_or_else(|| Utc::now().timestamp()),
+                };
+                event.id = db::insert_container_event(&event)?;
+                // Sending only fails when nobody is subscribed
+                let _ = LIVE.send(event);
+            }
+        }
+    }
+}
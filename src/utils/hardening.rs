@@ -0,0 +1,105 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::collections::HashMap;
+
+use bollard::secret::{HostConfig, ResourcesUlimits};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::config::PlanConfig;
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Ulimit {
+    pub name: String,
+    pub soft: i64,
+    pub hard: i64,
+}
+
+// Security settings applied to every tenant container
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct HardeningProfile {
+    pub cap_drop: Vec<String>,
+    pub cap_add: Vec<String>,
+    pub no_new_privileges: bool,
+    pub pids_limit: i64,
+    pub read_only_rootfs: bool,
+    // Only mounted when read_only_rootfs is enabled
+    pub tmpfs: HashMap<String, String>,
+    pub ulimits: Vec<Ulimit>,
+    pub userns_mode: Option<String>,
+    // e.g. "runsc" to run containers under gVisor
+    pub runtime: Option<String>,
+}
+
+impl Default for HardeningProfile {
+    fn default() -> Self {
+        Self {
+            cap_drop: vec!["ALL".to_string()],
+            cap_add: [
+                "CHOWN",
+                "DAC_OVERRIDE",
+                "FOWNER",
+                "SETGID",
+                "SETUID",
+                "NET_BIND_SERVICE",
+                "KILL",
+            ]
+            .iter()
+            .map(|cap| cap.to_string())
+            .collect(),
+            no_new_privileges: true,
+            pids_limit: 512,
+            read_only_rootfs: false,
+            tmpfs: HashMap::from([("/tmp".to_string(), "rw,noexec,nosuid,size=64m".to_string())]),
+            ulimits: vec![Ulimit {
+                name: "nofile".to_string(),
+                soft: 4096,
+                hard: 8192,
+            }],
+            userns_mode: None,
+            runtime: None,
+        }
+    }
+}
+
+static CONFIG: Lazy<PlanConfig<HardeningProfile>> =
+    Lazy::new(|| PlanConfig::load("HARDENING_CONFIG"));
+
+pub fn plan_exists(plan: &str) -> bool {
+    CONFIG.has_plan(plan)
+}
+
+pub fn profile_for_user(username: &str) -> HardeningProfile {
+    CONFIG.for_user(username)
+}
+
+pub fn apply(profile: &HardeningProfile, host_config: &mut HostConfig) {
+    host_config.cap_drop = Some(profile.cap_drop.clone());
+    host_config.cap_add = Some(profile.cap_add.clone());
+    if profile.no_new_privileges {
+        host_config.security_opt = Some(vec!["no-new-privileges:true".to_string()]);
+    }
+    host_config.pids_limit = Some(profile.pids_limit);
+    if profile.read_only_rootfs {
+        host_config.readonly_rootfs = Some(true);
+        host_config.tmpfs = Some(profile.tmpfs.clone());
+    }
+    host_config.ulimits = Some(
+        profile
+            .ulimits
+            .iter()
+            .map(|ulimit| ResourcesUlimits {
+                name: Some(ulimit.name.clone()),
+                soft: Some(ulimit.soft),
+                hard: Some(ulimit.hard),
+            })
+            .collect(),
+    );
+    host_config.userns_mode = profile.userns_mode.clone();
+    host_config.runtime = profile.runtime.clone();
+}
@@ -0,0 +1,226 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::{error::Error, time::Duration};
+
+use bollard::{
+    container::InspectContainerOptions,
+    secret::{HealthConfig, HealthStatusEnum},
+    Docker,
+};
+use chrono::Utc;
+use dotenvy::var;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::utils::{db, mail, orgs, webhook};
+
+static PATH_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^/[A-Za-z0-9._~/?=&%+-]{0,511}$").unwrap());
+static CHECK_INTERVAL: Lazy<u64> = Lazy::new(|| {
+    var("HEALTH_CHECK_INTERVAL")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(30)
+});
+const NANOS: i64 = 1_000_000_000;
+const MAX_COMMAND_ARGS: usize = 32;
+const MAX_COMMAND_ARG_LEN: usize = 1024;
+// Set when Dockify stops a container so the exit isn't reported
+pub const STOPPED: &str = "stopped";
+const EXITED: &str = "exited";
+const RUNNING: &str = "running";
+
+fn default_interval() -> i64 {
+    30
+}
+fn default_timeout() -> i64 {
+    5
+}
+fn default_retries() -> i64 {
+    3
+}
+
+// Exactly one of http or command has to be given, durations are in seconds
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct HealthCheck {
+    // Path requested from the container's port 80, e.g. "/healthz"
+    pub http: Option<String>,
+    // Run inside the container, a non-zero exit code is unhealthy
+    pub command: Option<Vec<String>>,
+    #[serde(default = "default_interval")]
+    pub interval: i64,
+    #[serde(default = "default_timeout")]
+    pub timeout: i64,
+    #[serde(default = "default_retries")]
+    pub retries: i64,
+    #[serde(default)]
+    pub start_period: i64,
+}
+
+impl HealthCheck {
+    pub fn validate(&self) -> Result<(), String> {
+        match (&self.http, &self.command) {
+            (Some(path), None) => {
+                if !PATH_REGEX.is_match(path) {
+                    return Err("healthcheck.http must be a path starting with /.".to_string());
+                }
+            }
+            (None, Some(command)) => {
+                if command.is_empty()
+                    || command.len() > MAX_COMMAND_ARGS
+                    || command
+                        .iter()
+                        .any(|arg| arg.len() > MAX_COMMAND_ARG_LEN || arg.contains('\0'))
+                {
+                    return Err("healthcheck.command is invalid.".to_string());
+                }
+            }
+            _ => return Err("healthcheck needs exactly one of http or command.".to_string()),
+        }
+        if !(5..=3600).contains(&self.interval) {
+            return Err("healthcheck.interval must be between 5 and 3600 seconds.".to_string());
+        }
+        if !(1..=self.interval).contains(&self.timeout) {
+            return Err(
+                "healthcheck.timeout must be between 1 second and the interval.".to_string(),
+            );
+        }
+        if !(1..=10).contains(&self.retries) {
+            return Err("healthcheck.retries must be between 1 and 10.".to_string());
+        }
+        if !(0..=600).contains(&self.start_period) {
+            return Err("healthcheck.start_period must be at most 600 seconds.".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn to_config(&self) -> HealthConfig {
+        let test = match (&self.http, &self.command) {
+            // The image has to ship wget or curl for HTTP checks
+            (Some(path), _) => vec![
+                "CMD-SHELL".to_string(),
+                format!(
+                    "wget -q -O /dev/null 'http://127.0.0.1:80{0}' || curl -fsS -o /dev/null 'http://127.0.0.1:80{0}' || exit 1",
+                    path
+                ),
+            ],
+            (None, command) => {
+                let mut test = vec!["CMD".to_string()];
+                test.extend(command.clone().unwrap_or_default());
+                test
+            }
+        };
+        HealthConfig {
+            test: Some(test),
+            interval: Some(self.interval * NANOS),
+            timeout: Some(self.timeout * NANOS),
+            retries: Some(self.retries),
+            start_period: Some(self.start_period * NANOS),
+            start_interval: None,
+        }
+    }
+}
+
+pub async fn run_health_monitor() {
+    let mut interval = tokio::time::interval(Duration::from_secs(*CHECK_INTERVAL));
+    loop {
+        interval.tick().await;
+        if let Err(err) = check_health().await {
+            eprintln!("Error while checking container health: {}", err);
+        }
+    }
+}
+
+async fn check_health() -> Result<(), Box<dyn Error>> {
+    let docker = Docker::connect_with_local_defaults()?;
+    for status in db::get_container_health()? {
+        let state = match docker
+            .inspect_container(&status.id, None::<InspectContainerOptions>)
+            .await
+        {
+            Ok(container) => match container.state {
+                Some(state) => state,
+                None => continue,
+            },
+            Err(_) => continue,
+        };
+        let current = if state.running.unwrap_or(false) {
+            match state.health.and_then(|health| health.status) {
+                Some(HealthStatusEnum::HEALTHY) => "healthy",
+                Some(HealthStatusEnum::UNHEALTHY) => "unhealthy",
+                Some(HealthStatusEnum::STARTING) => "starting",
+                _ => RUNNING,
+            }
+        } else if status.health.as_deref() == Some(STOPPED) || status.sleeping {
+            STOPPED
+        } else {
+            EXITED
+        };
+        if status.health.as_deref() == Some(current) {
+            continue;
+        }
+        db::set_container_health(&status.name, Some(current))?;
+        let detail = match current {
+            "unhealthy" => "is failing its health check".to_string(),
+            EXITED => format!(
+                "exited unexpectedly with code {}{}",
+                state.exit_code.unwrap_or(0),
+                if state.oom_killed.unwrap_or(false) {
+                    " (out of memory)"
+                } else {
+                    ""
+                }
+            ),
+            _ => continue,
+        };
+        notify(&status.username, &status.name, current, &detail).await;
+    }
+    Ok(())
+}
+
+// Emails and calls the webhook of the account's owner, per their notification settings
+pub async fn notify(username: &str, container: &str, event: &str, detail: &str) {
+    println!("Container '{}' of {} {}", container, username, detail);
+    // Org containers notify the org's owner
+    let username = &orgs::contact(username);
+    let (email, webhook) = match db::get_notification_settings(username) {
+        Ok(settings) => settings,
+        Err(err) => {
+            eprintln!("Error getting notification settings: {}", err);
+            return;
+        }
+    };
+    if email {
+        let sent = db::get_user_info(username).map(|(_, _, _, address)| {
+            mail::send(
+                &address,
+                &format!("Dockify: container {} is {}", container, event),
+                format!("Your container '{}' {}.", container, detail),
+            )
+        });
+        match sent {
+            Ok(Err(err)) => eprintln!("Error sending health email: {}", err),
+            Err(err) => eprintln!("Error getting user email: {}", err),
+            _ => (),
+        }
+    }
+    if let Some(url) = webhook {
+        let payload = json!({
+            "container": container,
+            "event": event,
+            "detail": detail,
+            "timestamp": Utc::now().timestamp(),
+        });
+        // Checked again on every send, as the host may have started resolving elsewhere
+        if let Err(err) = webhook::post(&url, &payload).await {
+            eprintln!("Error calling health webhook of {}: {}", username, err);
+        }
+    }
+}
@@ -0,0 +1,168 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    time::{Duration, Instant},
+};
+
+use bollard::{container::StatsOptions, Docker};
+use futures_util::StreamExt;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpStream;
+
+use crate::utils::{bandwidth, config::PlanConfig, container, db};
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdlePolicy {
+    pub enabled: bool,
+    // Containers below both thresholds are considered idle
+    pub cpu_percent: f64,
+    pub network_bytes_per_minute: i64,
+    pub minutes: u64,
+}
+
+impl Default for IdlePolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            cpu_percent: 2.0,
+            network_bytes_per_minute: 64 * 1024,
+            minutes: 30,
+        }
+    }
+}
+
+static CONFIG: Lazy<PlanConfig<IdlePolicy>> = Lazy::new(|| PlanConfig::load("IDLE_CONFIG"));
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const WAKE_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn plan_exists(plan: &str) -> bool {
+    CONFIG.has_plan(plan)
+}
+
+struct Sample {
+    network_bytes: i64,
+    idle_since: Option<Instant>,
+}
+
+pub async fn run_idle_detector() {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    // Container id -> last sample, idle timers restart with the backend
+    let mut samples: HashMap<String, Sample> = HashMap::new();
+    loop {
+        interval.tick().await;
+        if let Err(err) = check_idle(&mut samples).await {
+            eprintln!("Error while checking for idle containers: {}", err);
+        }
+    }
+}
+
+async fn check_idle(samples: &mut HashMap<String, Sample>) -> Result<(), Box<dyn Error>> {
+    let docker = Docker::connect_with_local_defaults()?;
+    let mut seen: Vec<String> = Vec::new();
+    for (id, username) in db::get_all_container_owners()? {
+        let policy = CONFIG.for_user(&username);
+        if !policy.enabled {
+            continue;
+        }
+        // one_shot is left off so Docker fills in precpu_stats for the CPU delta
+        let stats = match docker
+            .stats(
+                &id,
+                Some(StatsOptions {
+                    stream: false,
+                    one_shot: false,
+                }),
+            )
+            .next()
+            .await
+        {
+            Some(Ok(stats)) => stats,
+            _ => continue,
+        };
+        // Stopped containers report no networks
+        let networks = match stats.networks {
+            Some(networks) if !networks.is_empty() => networks,
+            _ => continue,
+        };
+        seen.push(id.clone());
+        let network_bytes = networks.values().fold(0, |total, network| {
+            total + network.rx_bytes as i64 + network.tx_bytes as i64
+        });
+        let cpu_delta = stats
+            .cpu_stats
+            .cpu_usage
+            .total_usage
+            .saturating_sub(stats.precpu_stats.cpu_usage.total_usage)
+            as f64;
+        let system_delta = stats
+            .cpu_stats
+            .system_cpu_usage
+            .unwrap_or(0)
+            .saturating_sub(stats.precpu_stats.system_cpu_usage.unwrap_or(0))
+            as f64;
+        let cpus = stats.cpu_stats.online_cpus.unwrap_or(1) as f64;
+        let cpu_percent = if system_delta > 0.0 {
+            cpu_delta / system_delta * cpus * 100.0
+        } else {
+            0.0
+        };
+
+        let sample = samples.entry(id.clone()).or_insert(Sample {
+            network_bytes,
+            idle_since: None,
+        });
+        let network_delta = if network_bytes >= sample.network_bytes {
+            network_bytes - sample.network_bytes
+        } else {
+            network_bytes
+        };
+        let per_minute = network_delta * 60 / CHECK_INTERVAL.as_secs() as i64;
+        sample.network_bytes = network_bytes;
+        if cpu_percent >= policy.cpu_percent || per_minute >= policy.network_bytes_per_minute {
+            sample.idle_since = None;
+            continue;
+        }
+        let idle_since = *sample.idle_since.get_or_insert_with(Instant::now);
+        if idle_since.elapsed() < Duration::from_secs(policy.minutes * 60) {
+            continue;
+        }
+        let name = match db::get_container_name(&id) {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+        println!("Container '{}' has been idle, putting it to sleep.", name);
+        match container::stop_container(&name).await {
+            Ok(_) => {
+                db::set_sleeping(&name, true)?;
+                samples.remove(&id);
+            }
+            Err(err) => eprintln!("Error stopping idle container: {}", err),
+        }
+    }
+    samples.retain(|id, _| seen.contains(id));
+    Ok(())
+}
+
+// Starts a sleeping container and waits until its published port accepts connections
+pub async fn wake(name: &str, username: &str, port: u16) -> Result<(), Box<dyn Error>> {
+    container::start_container(name).await?;
+    db::set_sleeping(name, false)?;
+    let docker = Docker::connect_with_local_defaults()?;
+    bandwidth::apply_rate_limit(&docker, name, username).await;
+    let started = Instant::now();
+    while started.elapsed() < WAKE_TIMEOUT {
+        if TcpStream::connect(("127.0.0.1", port)).await.is_ok() {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+    Err("Container did not start listening in time".into())
+}
@@ -0,0 +1,152 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::net::IpAddr;
+
+use chrono::Utc;
+use dotenvy::var;
+use once_cell::sync::Lazy;
+
+use axum::http::StatusCode;
+
+use crate::utils::{
+    db, mail,
+    res::{m_resp, retry_resp, Respond},
+};
+
+// Longest delay between attempts before the lockout threshold is reached
+const MAX_BACKOFF_SECS: i64 = 15 * 60;
+// Failures older than this are forgotten
+const FAILURE_WINDOW_SECS: i64 = 24 * 60 * 60;
+
+fn env_or(name: &str, default: i64) -> i64 {
+    var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+// Failures allowed before each further attempt has to wait, doubling every time
+static FREE_ATTEMPTS: Lazy<i64> = Lazy::new(|| env_or("LOGIN_FREE_ATTEMPTS", 3));
+static ACCOUNT_LOCKOUT_ATTEMPTS: Lazy<i64> = Lazy::new(|| env_or("ACCOUNT_LOCKOUT_ATTEMPTS", 10));
+// Higher than the account limit since many users can share an address
+static IP_LOCKOUT_ATTEMPTS: Lazy<i64> = Lazy::new(|| env_or("IP_LOCKOUT_ATTEMPTS", 50));
+static LOCKOUT_MINUTES: Lazy<i64> = Lazy::new(|| env_or("LOCKOUT_MINUTES", 30));
+
+pub fn user_key(username: &str) -> String {
+    format!("user:{}", username)
+}
+pub fn ip_key(ip: &IpAddr) -> String {
+    format!("ip:{}", ip)
+}
+
+// Seconds until the account or address may try again, None if it can try now
+pub fn check(username: Option<&str>, ip: &IpAddr) -> rusqlite::Result<Option<i64>> {
+    let now = Utc::now().timestamp();
+    let mut keys = vec![ip_key(ip)];
+    if let Some(username) = username {
+        keys.push(user_key(username));
+    }
+    let mut wait = 0;
+    for key in keys {
+        if let Some((_, locked_until, _)) = db::get_login_failures(&key)? {
+            wait = wait.max(locked_until - now);
+        }
+    }
+    Ok((wait > 0).then_some(wait))
+}
+
+// Rejects the login with a 429 while the account or address has to wait
+pub fn guard(username: Option<&str>, ip: &IpAddr) -> Result<(), Respond> {
+    match check(username, ip) {
+        Ok(None) => Ok(()),
+        Ok(Some(wait)) => Err(retry_resp(
+            wait,
+            format!(
+                "Too many failed login attempts, try again in {} seconds.",
+                wait
+            ),
+        )),
+        Err(err) => {
+            eprintln!("Error while checking failed logins: {}", err);
+            Err(m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            ))
+        }
+    }
+}
+
+// Counts a failed attempt against the address and, when known, the account
+pub fn record_failure(username: Option<&str>, ip: &IpAddr) {
+    if let Some(username) = username {
+        match count(&user_key(username), *ACCOUNT_LOCKOUT_ATTEMPTS) {
+            Ok(true) => notify(username, ip),
+            Ok(false) => (),
+            Err(err) => eprintln!(
+                "Error while recording failed login for {}: {}",
+                username, err
+            ),
+        }
+    }
+    if let Err(err) = count(&ip_key(ip), *IP_LOCKOUT_ATTEMPTS) {
+        eprintln!("Error while recording failed login from {}: {}", ip, err);
+    }
+}
+
+// A full login resets the account's counter. The address keeps its own, so logging
+// into one account doesn't clear failures spread across others from the same place.
+pub fn record_success(username: &str) {
+    let key = user_key(username);
+    if let Err(err) = db::clear_login_failures(&key) {
+        eprintln!("Error while clearing failed logins for {}: {}", key, err);
+    }
+}
+
+// Returns true when this failure is the one that locks the key
+fn count(key: &str, lockout_attempts: i64) -> rusqlite::Result<bool> {
+    let now = Utc::now().timestamp();
+    let failures = match db::get_login_failures(key)? {
+        Some((failures, _, last_failure)) if now - last_failure < FAILURE_WINDOW_SECS => {
+            failures + 1
+        }
+        _ => 1,
+    };
+    let locked_until = if failures >= lockout_attempts {
+        now + *LOCKOUT_MINUTES * 60
+    } else if failures > *FREE_ATTEMPTS {
+        let exponent = (failures - *FREE_ATTEMPTS - 1).min(16) as u32;
+        now + 2_i64.pow(exponent).min(MAX_BACKOFF_SECS)
+    } else {
+        0
+    };
+    db::set_login_failures(key, failures, locked_until, now)?;
+    Ok(failures == lockout_attempts)
+}
+
+fn notify(username: &str, ip: &IpAddr) {
+    let email = match db::get_user_info(username) {
+        Ok((_, _, _, email)) => email,
+        Err(err) => {
+            eprintln!(
+                "Error while getting email for lockout of {}: {}",
+                username, err
+            );
+            return;
+        }
+    };
+    let body = format!(
+        "Hello {},\n\nYour Dockify account has been locked for {} minutes after {} failed login attempts, the last one from {}.\n\nIf this wasn't you, consider resetting your password. Contact support if you need the account unlocked sooner.",
+        username, *LOCKOUT_MINUTES, *ACCOUNT_LOCKOUT_ATTEMPTS, ip
+    );
+    if let Err(err) = mail::send(&email, "Your Dockify account has been locked", body) {
+        eprintln!("Error while sending lockout email to {}: {}", username, err);
+    }
+}
+
+pub fn purge_expired() -> rusqlite::Result<usize> {
+    db::purge_login_failures(Utc::now().timestamp() - FAILURE_WINDOW_SECS)
+}
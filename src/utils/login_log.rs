@@ -0,0 +1,99 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::net::IpAddr;
+
+use axum::http::{header, HeaderMap};
+use chrono::{TimeZone, Utc};
+
+use crate::utils::{
+    db::{self, LoginEvent},
+    mail,
+};
+
+// Login history is kept for this many days
+const RETENTION_DAYS: i64 = 90;
+const MAX_USER_AGENT: usize = 256;
+
+pub enum Method {
+    Password,
+    Mfa,
+    Oidc,
+}
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Password => "password",
+            Method::Mfa => "2fa",
+            Method::Oidc => "oidc",
+        }
+    }
+}
+
+// Logging failures are printed but never fail the login itself
+pub fn record(username: &str, ip: &IpAddr, headers: &HeaderMap, method: Method, success: bool) {
+    let event = LoginEvent {
+        ip: ip.to_string(),
+        user_agent: headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|agent| agent.chars().take(MAX_USER_AGENT).collect()),
+        method: method.as_str().to_string(),
+        success,
+        created: Utc::now().timestamp(),
+    };
+    // Checked first so the event being recorded doesn't count as a known address
+    let new_ip = success
+        && match db::get_known_login(username, &event.ip) {
+            Ok((any, known)) => any && !known,
+            Err(err) => {
+                eprintln!(
+                    "Error while checking known logins for {}: {}",
+                    username, err
+                );
+                false
+            }
+        };
+    if let Err(err) = db::insert_login_event(username, &event) {
+        eprintln!("Error while recording login for {}: {}", username, err);
+    }
+    if new_ip {
+        alert(username, &event);
+    }
+}
+
+fn alert(username: &str, event: &LoginEvent) {
+    let email = match db::get_user_info(username) {
+        Ok((_, _, _, email)) => email,
+        Err(err) => {
+            eprintln!(
+                "Error while getting email for login alert of {}: {}",
+                username, err
+            );
+            return;
+        }
+    };
+    let time = Utc
+        .timestamp_opt(event.created, 0)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default();
+    let body = format!(
+        "Hello {},\n\nYour Dockify account was signed in to from a new IP address.\n\nIP address: {}\nDevice: {}\nTime: {}\n\nIf this wasn't you, reset your password and sign out of all sessions.",
+        username,
+        event.ip,
+        event.user_agent.as_deref().unwrap_or("Unknown"),
+        time
+    );
+    if let Err(err) = mail::send(&email, "New sign-in to your Dockify account", body) {
+        eprintln!("Error while sending login alert to {}: {}", username, err);
+    }
+}
+
+pub fn purge_expired() -> rusqlite::Result<usize> {
+    db::purge_login_events(Utc::now().timestamp() - RETENTION_DAYS * 24 * 60 * 60)
+}
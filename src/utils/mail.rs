@@ -0,0 +1,39 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::error::Error;
+
+use dotenvy::var;
+use lettre::message::{header, Message};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{SmtpTransport, Transport};
+use once_cell::sync::Lazy;
+
+static SMTP_USER: Lazy<String> =
+    Lazy::new(|| var("SMTP_USER").expect("Failed to retrieve SMTP_USER"));
+static SMTP_PASS: Lazy<String> =
+    Lazy::new(|| var("SMTP_PASS").expect("Failed to retrieve SMTP_PASS"));
+static CREDS: Lazy<Credentials> =
+    Lazy::new(|| Credentials::new(SMTP_USER.clone(), SMTP_PASS.clone()));
+
+pub static MAILER: Lazy<SmtpTransport> = Lazy::new(|| {
+    SmtpTransport::relay("mail.smtp2go.com")
+        .expect("Failed to connect to SMTP relay")
+        .credentials(CREDS.clone())
+        .build()
+});
+
+// Sends a plain text email from the account address
+pub fn send(to: &str, subject: &str, body: String) -> Result<(), Box<dyn Error>> {
+    let email = Message::builder()
+        .from("account@dockify.xyz".parse()?)
+        .to(to.parse()?)
+        .subject(subject)
+        .header(header::ContentType::TEXT_PLAIN)
+        .body(body)?;
+    MAILER.send(&email)?;
+    Ok(())
+}
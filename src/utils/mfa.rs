@@ -0,0 +1,113 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::error::Error;
+
+use chrono::{Duration, Utc};
+use rand::{distributions::Alphanumeric, Rng};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::utils::{
+    db, secrets,
+    validation::{self, Claims},
+};
+
+const ISSUER: &str = "Dockify";
+const STEP: i64 = 30;
+const RECOVERY_CODES: usize = 10;
+pub const CHALLENGE_MINUTES: i64 = 5;
+const CHALLENGE_PURPOSE: &str = "mfa";
+
+fn totp(secret: &str, username: &str) -> Result<TOTP, Box<dyn Error>> {
+    Ok(TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        STEP as u64,
+        Secret::Encoded(secret.to_string()).to_bytes()?,
+        Some(ISSUER.to_string()),
+        username.replace(':', ""),
+    )?)
+}
+
+// Stores a new pending secret and returns its otpauth URI and base32 secret
+pub fn enroll(username: &str) -> Result<(String, String), Box<dyn Error>> {
+    let bytes: [u8; 20] = rand::thread_rng().gen();
+    let secret = Secret::Raw(bytes.to_vec()).to_encoded().to_string();
+    let totp = totp(&secret, username)?;
+    let (nonce, ciphertext) =
+        secrets::encrypt(&secret).map_err(|_| "Failed to encrypt TOTP secret")?;
+    db::set_pending_mfa(username, &nonce, &ciphertext)?;
+    Ok((totp.get_url(), secret))
+}
+
+// Checks a code against the user's secret, each time step can only be used once
+pub fn check_code(username: &str, code: &str) -> Result<bool, Box<dyn Error>> {
+    let Some(mfa) = db::get_mfa(username)? else {
+        return Ok(false);
+    };
+    let secret =
+        secrets::decrypt(&mfa.nonce, &mfa.secret).map_err(|_| "Failed to decrypt TOTP secret")?;
+    let totp = totp(&secret, username)?;
+    let now = Utc::now().timestamp();
+    for step in [now / STEP - 1, now / STEP, now / STEP + 1] {
+        if mfa.last_step.is_some_and(|last| step <= last) {
+            continue;
+        }
+        if totp.generate((step * STEP) as u64) == code {
+            db::set_mfa_step(username, step)?;
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// Replaces the user's recovery codes, only their argon2 hashes are stored
+pub fn generate_recovery_codes(username: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut codes = Vec::new();
+    let mut hashes = Vec::new();
+    for _ in 0..RECOVERY_CODES {
+        let code: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(10)
+            .map(|c| (c as char).to_ascii_lowercase())
+            .collect();
+        let code = format!("{}-{}", &code[..5], &code[5..]);
+        hashes.push(validation::hash_password(&code).map_err(|err| err.to_string())?);
+        codes.push(code);
+    }
+    db::set_recovery_codes(username, &hashes)?;
+    Ok(codes)
+}
+
+// Consumes a matching recovery code
+pub fn use_recovery_code(username: &str, code: &str) -> Result<bool, Box<dyn Error>> {
+    let code = code.trim().to_ascii_lowercase();
+    for (id, hash) in db::get_recovery_codes(username)? {
+        if validation::verify_password(&code, &hash).is_ok() {
+            db::delete_recovery_code(id)?;
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// Short-lived token proving the password step of a login succeeded
+pub fn challenge_token(username: &str) -> Result<String, jsonwebtoken::errors::Error> {
+    let now = Utc::now();
+    validation::encode_claims(&Claims {
+        sub: username.to_string(),
+        exp: (now + Duration::minutes(CHALLENGE_MINUTES)).timestamp() as usize,
+        iat: now.timestamp() as usize,
+        sid: None,
+        purpose: Some(CHALLENGE_PURPOSE.to_string()),
+    })
+}
+
+pub async fn verify_challenge(token: &str) -> Option<String> {
+    let claims = validation::verify_jwt(token).await.ok()?;
+    (claims.purpose.as_deref() == Some(CHALLENGE_PURPOSE)).then_some(claims.sub)
+}
@@ -0,0 +1,102 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::collections::HashMap;
+
+use bollard::{
+    errors::Error,
+    network::{ConnectNetworkOptions, CreateNetworkOptions, InspectNetworkOptions},
+    secret::EndpointSettings,
+    Docker,
+};
+use rand::distributions::{Alphanumeric, DistString};
+
+use crate::utils::db;
+
+// Each user's containers share a private bridge network. Docker isolates
+// user-defined bridges from each other, so tenants cannot reach one another,
+// while containers on the same network resolve each other by name.
+pub async fn ensure_user_network(docker: &Docker, username: &str) -> Result<String, Error> {
+    if let Ok(network) = db::get_user_network(username) {
+        if docker
+            .inspect_network(&network, None::<InspectNetworkOptions<String>>)
+            .await
+            .is_ok()
+        {
+            return Ok(network);
+        }
+    }
+    let network = format!(
+        "dockify-user-{}",
+        Alphanumeric
+            .sample_string(&mut rand::thread_rng(), 16)
+            .to_lowercase()
+    );
+    docker
+        .create_network(CreateNetworkOptions {
+            name: network.clone(),
+            driver: "bridge".to_string(),
+            labels: HashMap::from([("dockify.user".to_string(), username.to_string())]),
+            ..Default::default()
+        })
+        .await?;
+    if let Err(err) = db::set_user_network(username, &network) {
+        eprintln!("Error storing user network: {}", err);
+        let _ = docker.remove_network(&network).await;
+        return Err(Error::DockerResponseServerError {
+            status_code: 500,
+            message: "Failed to store user network".to_string(),
+        });
+    }
+    println!("Created network '{}' for {}", network, username);
+    Ok(network)
+}
+
+pub fn endpoint(alias: &str) -> EndpointSettings {
+    EndpointSettings {
+        aliases: Some(vec![alias.to_string()]),
+        ..Default::default()
+    }
+}
+
+pub async fn connect_to_user_network(
+    docker: &Docker,
+    network: &str,
+    container: &str,
+) -> Result<(), Error> {
+    docker
+        .connect_network(
+            network,
+            ConnectNetworkOptions {
+                container: container.to_string(),
+                endpoint_config: endpoint(container),
+            },
+        )
+        .await
+}
+
+// Called after deleting containers, removes the network once nothing uses it
+pub async fn remove_user_network_if_unused(docker: &Docker, username: &str) {
+    match db::count_containers_by_username(username) {
+        Ok(0) => (),
+        Ok(_) => return,
+        Err(err) => {
+            eprintln!("Error counting user's containers: {}", err);
+            return;
+        }
+    }
+    let network = match db::get_user_network(username) {
+        Ok(network) => network,
+        Err(_) => return,
+    };
+    if let Err(err) = docker.remove_network(&network).await {
+        eprintln!("Error removing user network: {}", err);
+        return;
+    }
+    if let Err(err) = db::delete_user_network(username) {
+        eprintln!("Error removing user network from DB: {}", err);
+    }
+}
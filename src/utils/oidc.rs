@@ -0,0 +1,249 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::{error::Error, str::FromStr, time::Duration};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use dotenvy::var;
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use once_cell::sync::Lazy;
+use rand::Rng;
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::sync::OnceCell;
+
+use crate::utils::{db, signup_policy, validation};
+
+const STATE_MINUTES: i64 = 10;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Signature algorithms accepted for ID tokens, shared secrets never are
+const ALLOWED_ALGORITHMS: [Algorithm; 7] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+];
+
+pub struct OidcConfig {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    // Must point at /api/oidc/callback and be registered with the provider
+    pub redirect_uri: String,
+    // Where the browser is sent with the session after logging in
+    pub frontend_redirect: String,
+}
+
+// OIDC login is disabled unless OIDC_ISSUER is set
+pub static CONFIG: Lazy<Option<OidcConfig>> = Lazy::new(|| {
+    let issuer = var("OIDC_ISSUER").ok()?;
+    Some(OidcConfig {
+        issuer: issuer.trim_end_matches('/').to_string(),
+        client_id: var("OIDC_CLIENT_ID").expect("Failed to retrieve OIDC_CLIENT_ID"),
+        client_secret: var("OIDC_CLIENT_SECRET").expect("Failed to retrieve OIDC_CLIENT_SECRET"),
+        redirect_uri: var("OIDC_REDIRECT_URI").expect("Failed to retrieve OIDC_REDIRECT_URI"),
+        frontend_redirect: var("OIDC_FRONTEND_REDIRECT")
+            .unwrap_or_else(|_| "https://dockify.xyz/login".to_string()),
+    })
+});
+static DISCOVERY: OnceCell<Discovery> = OnceCell::const_new();
+static CLIENT: Lazy<Client> = Lazy::new(|| {
+    Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("Failed to build HTTP client")
+});
+
+#[derive(Deserialize)]
+struct Discovery {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Deserialize)]
+pub struct IdClaims {
+    pub sub: String,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub preferred_username: Option<String>,
+    nonce: Option<String>,
+}
+
+async fn discovery(config: &OidcConfig) -> Result<&'static Discovery, Box<dyn Error>> {
+    DISCOVERY
+        .get_or_try_init(|| async {
+            let discovery: Discovery = CLIENT
+                .get(format!(
+                    "{}/.well-known/openid-configuration",
+                    config.issuer
+                ))
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            if discovery.issuer.trim_end_matches('/') != config.issuer {
+                return Err::<Discovery, Box<dyn Error>>(
+                    "Discovered issuer does not match OIDC_ISSUER".into(),
+                );
+            }
+            Ok(discovery)
+        })
+        .await
+}
+
+fn pkce_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+// Builds the provider's authorization URL, remembering state, nonce and PKCE verifier
+pub async fn authorization_url(config: &OidcConfig) -> Result<String, Box<dyn Error>> {
+    let discovery = discovery(config).await?;
+    let state = validation::generate_token();
+    let nonce = validation::generate_token();
+    let verifier = validation::generate_token();
+    db::insert_oidc_state(&state, &nonce, &verifier, Utc::now().timestamp())?;
+    let url = Url::parse_with_params(
+        &discovery.authorization_endpoint,
+        &[
+            ("response_type", "code"),
+            ("client_id", &config.client_id),
+            ("redirect_uri", &config.redirect_uri),
+            ("scope", "openid email profile"),
+            ("state", &state),
+            ("nonce", &nonce),
+            ("code_challenge", &pkce_challenge(&verifier)),
+            ("code_challenge_method", "S256"),
+        ],
+    )?;
+    Ok(url.to_string())
+}
+
+// Exchanges the authorization code and returns the validated ID token claims
+pub async fn exchange(
+    config: &OidcConfig,
+    code: &str,
+    state: &str,
+) -> Result<IdClaims, Box<dyn Error>> {
+    let since = Utc::now().timestamp() - STATE_MINUTES * 60;
+    let (nonce, verifier) = db::take_oidc_state(state, since)?.ok_or("Unknown or expired state")?;
+    let discovery = discovery(config).await?;
+    let tokens: TokenResponse = CLIENT
+        .post(&discovery.token_endpoint)
+        .basic_auth(&config.client_id, Some(&config.client_secret))
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &config.redirect_uri),
+            ("code_verifier", &verifier),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let header = decode_header(&tokens.id_token)?;
+    let jwks: JwkSet = CLIENT
+        .get(&discovery.jwks_uri)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let jwk = match &header.kid {
+        Some(kid) => jwks.find(kid),
+        None => jwks.keys.first(),
+    }
+    .ok_or("No matching signing key")?;
+    // The key decides the algorithm, a token can't pick its own
+    let algorithm = match &jwk.common.key_algorithm {
+        Some(key_algorithm) => Algorithm::from_str(&key_algorithm.to_string())?,
+        None => Algorithm::RS256,
+    };
+    if !ALLOWED_ALGORITHMS.contains(&algorithm) || header.alg != algorithm {
+        return Err("ID token algorithm is not allowed".into());
+    }
+    let mut validation = Validation::new(algorithm);
+    validation.set_audience(&[&config.client_id]);
+    validation.set_issuer(&[&discovery.issuer]);
+    let claims =
+        decode::<IdClaims>(&tokens.id_token, &DecodingKey::from_jwk(jwk)?, &validation)?.claims;
+    if claims.nonce.as_deref() != Some(nonce.as_str()) {
+        return Err("ID token nonce does not match".into());
+    }
+    Ok(claims)
+}
+
+// Finds the Dockify user for an identity, linking by verified email or creating
+// a new account when needed
+pub fn resolve_user(config: &OidcConfig, claims: &IdClaims) -> Result<String, Box<dyn Error>> {
+    if let Some(username) = db::get_oidc_identity(&config.issuer, &claims.sub)? {
+        return Ok(username);
+    }
+    let email = match &claims.email {
+        Some(email) if claims.email_verified => email.to_lowercase(),
+        _ => return Err("Provider did not return a verified email".into()),
+    };
+    let username = match db::get_user_by_email(&email) {
+        Ok((verified, username)) => {
+            // The provider has verified the address for us. Whoever registered the
+            // unverified account may not own it, so their password stops working.
+            if verified == 0 {
+                let hash = validation::hash_password(validation::generate_token())
+                    .map_err(|err| err.to_string())?;
+                db::reset_password(&username, &hash)?;
+                db::verify_user(&username)?;
+            }
+            username
+        }
+        Err(rusqlite::Error::QueryReturnedNoRows) => provision(claims, &email)?,
+        Err(err) => return Err(err.into()),
+    };
+    db::insert_oidc_identity(&config.issuer, &claims.sub, &username)?;
+    Ok(username)
+}
+
+fn provision(claims: &IdClaims, email: &str) -> Result<String, Box<dyn Error>> {
+    // OIDC can't carry an invite code, so invite only mode blocks new accounts here
+    signup_policy::admit(email, None).map_err(|rejection| rejection.message())?;
+    let base: String = claims
+        .preferred_username
+        .as_deref()
+        .unwrap_or_else(|| email.split('@').next().unwrap_or(""))
+        .chars()
+        .filter(|c| validation::is_username_char(*c))
+        // Leaves room for the suffix added on collisions
+        .take(validation::MAX_USERNAME_LEN - 4)
+        .collect();
+    let base = if base.is_empty() {
+        "user".to_string()
+    } else {
+        base
+    };
+    let mut username = base.clone();
+    while db::check_exists(username.to_lowercase(), "username", "users")? {
+        username = format!("{}{}", base, rand::thread_rng().gen_range(1000..10000));
+    }
+    // The account can't log in with a password until one is set through a reset
+    let hash =
+        validation::hash_password(validation::generate_token()).map_err(|err| err.to_string())?;
+    db::insert_user(email, &username, &hash, true)?;
+    println!("Provisioned {} through OIDC.", username);
+    Ok(username.to_lowercase())
+}
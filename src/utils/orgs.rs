@@ -0,0 +1,128 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::http::{HeaderMap, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{
+    db,
+    res::{m_resp, Respond},
+};
+
+// Container routes act on an org's account instead of the user's when this header is set
+pub const ORG_HEADER: &str = "x-dockify-org";
+// Usernames can't contain ':' (see validation::validate_username), so org accounts
+// never collide with users
+const ACCOUNT_PREFIX: &str = "org:";
+
+// Ordered from least to most privileged
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum OrgRole {
+    Viewer,
+    Developer,
+    Admin,
+    Owner,
+}
+
+impl OrgRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrgRole::Viewer => "viewer",
+            OrgRole::Developer => "developer",
+            OrgRole::Admin => "admin",
+            OrgRole::Owner => "owner",
+        }
+    }
+    pub fn parse(role: &str) -> Option<OrgRole> {
+        match role {
+            "viewer" => Some(OrgRole::Viewer),
+            "developer" => Some(OrgRole::Developer),
+            "admin" => Some(OrgRole::Admin),
+            "owner" => Some(OrgRole::Owner),
+            _ => None,
+        }
+    }
+}
+
+// Containers, credits, secrets and schedules of an org are stored under this account
+pub fn account(slug: &str) -> String {
+    format!("{}{}", ACCOUNT_PREFIX, slug)
+}
+
+pub fn validate_slug(slug: &str) -> bool {
+    (3..=32).contains(&slug.len())
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !slug.starts_with('-')
+        && !slug.ends_with('-')
+}
+
+// The user notifications about an account go to
+pub fn contact(account: &str) -> String {
+    match account.strip_prefix(ACCOUNT_PREFIX) {
+        Some(slug) => match db::get_org_owner(slug) {
+            Ok(Some(owner)) => owner,
+            Ok(None) => account.to_string(),
+            Err(err) => {
+                eprintln!("Error while getting owner of org {}: {}", slug, err);
+                account.to_string()
+            }
+        },
+        None => account.to_string(),
+    }
+}
+
+pub fn member_role(slug: &str, username: &str) -> Result<Option<OrgRole>, Respond> {
+    match db::get_org_role(slug, username) {
+        Ok(role) => Ok(role.as_deref().and_then(OrgRole::parse)),
+        Err(err) => {
+            eprintln!("Error while getting org membership: {}", err);
+            Err(m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            ))
+        }
+    }
+}
+
+// Returns the user's role after checking it's at least `required`. Non-members get
+// a 404 so org names can't be probed.
+pub fn require_role(slug: &str, username: &str, required: OrgRole) -> Result<OrgRole, Respond> {
+    match member_role(slug, username)? {
+        Some(role) if role >= required => Ok(role),
+        Some(_) => Err(m_resp(
+            StatusCode::FORBIDDEN,
+            format!("This requires the {} role in the org.", required.as_str()),
+        )),
+        None => Err(m_resp(StatusCode::NOT_FOUND, "Org not found.")),
+    }
+}
+
+pub fn requested_org(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(ORG_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|org| !org.is_empty())
+}
+
+// The account a request acts on: the user's own, or the org given in the
+// x-dockify-org header if the user's role there is at least `required`
+pub fn resolve_account(
+    org: Option<&str>,
+    username: &str,
+    required: OrgRole,
+) -> Result<String, Respond> {
+    match org {
+        Some(slug) => {
+            let slug = slug.to_lowercase();
+            require_role(&slug, username, required)?;
+            Ok(account(&slug))
+        }
+        None => Ok(username.to_string()),
+    }
+}
@@ -0,0 +1,217 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::{
+    collections::HashMap,
+    fs,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dotenvy::var;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::utils::{auth::ClientIp, db, res::retry_resp, tokens, validation};
+
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct Budget {
+    pub requests: u32,
+    pub seconds: u64,
+}
+
+impl Budget {
+    fn per_second(&self) -> f64 {
+        self.requests as f64 / self.seconds.max(1) as f64
+    }
+}
+
+// Budgets are per client. Routes not listed share the default budget, a
+// trailing '*' matches every path with that prefix.
+#[derive(Deserialize)]
+pub struct RateLimits {
+    pub default: Budget,
+    #[serde(default)]
+    pub routes: HashMap<String, Budget>,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        let strict = Budget {
+            requests: 10,
+            seconds: 60,
+        };
+        let hourly = Budget {
+            requests: 5,
+            seconds: 60 * 60,
+        };
+        RateLimits {
+            default: Budget {
+                requests: 120,
+                seconds: 60,
+            },
+            routes: HashMap::from([
+                ("/api/login".to_string(), strict),
+                ("/api/login/2fa".to_string(), strict),
+                ("/api/oidc/*".to_string(), strict),
+                ("/api/signup".to_string(), hourly),
+                ("/api/password/forgot".to_string(), hourly),
+                ("/api/verify/resend".to_string(), hourly),
+                ("/api/account/password".to_string(), strict),
+                ("/api/account/email".to_string(), hourly),
+                ("/api/account/email/confirm".to_string(), strict),
+                ("/api/account/delete".to_string(), strict),
+            ]),
+        }
+    }
+}
+
+impl RateLimits {
+    // The most specific pattern matching the path, None for the default budget
+    fn route(&self, path: &str) -> (Option<&str>, Budget) {
+        if let Some((pattern, budget)) = self.routes.get_key_value(path) {
+            return (Some(pattern), *budget);
+        }
+        self.routes
+            .iter()
+            .filter_map(|(pattern, budget)| {
+                let prefix = pattern.strip_suffix('*')?;
+                path.starts_with(prefix)
+                    .then_some((pattern.as_str(), *budget))
+            })
+            .max_by_key(|(pattern, _)| pattern.len())
+            .map_or((None, self.default), |(pattern, budget)| {
+                (Some(pattern), budget)
+            })
+    }
+}
+
+// JSON file given by RATE_LIMIT_FILE, the built in limits are used without one
+static LIMITS: Lazy<RateLimits> = Lazy::new(|| match var("RATE_LIMIT_FILE") {
+    Ok(path) => {
+        let contents = fs::read_to_string(&path).expect("Failed to read RATE_LIMIT_FILE");
+        serde_json::from_str(&contents).expect("Failed to parse RATE_LIMIT_FILE")
+    }
+    Err(_) => RateLimits::default(),
+});
+
+// Reads the limits at startup, so a broken RATE_LIMIT_FILE stops the server from starting
+// instead of failing requests
+pub fn load() {
+    Lazy::force(&LIMITS);
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+// Keyed by client and route pattern
+static BUCKETS: Lazy<Mutex<HashMap<(String, String), Bucket>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// Signed in clients are limited per user or per access token, anyone else per address
+async fn client_key(headers: &HeaderMap, ip: &ClientIp) -> String {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(' ').nth(1));
+    if let Some(token) = token {
+        if tokens::is_token(token) {
+            let hash = validation::hash_token(token);
+            // Unknown tokens fall back to the address so they can't be rotated around the limit
+            if let Ok(Some(_)) = db::get_api_token_scopes(&hash) {
+                return format!("token:{}", hash);
+            }
+        } else if let Ok(claims) = validation::verify_jwt(token).await {
+            return format!("user:{}", claims.sub);
+        }
+    }
+    format!("ip:{}", ip.0)
+}
+
+struct Decision {
+    allowed: bool,
+    remaining: u32,
+    // Seconds until the next request is allowed, or until the bucket is full again
+    retry_after: u64,
+    reset: u64,
+}
+
+fn take(key: String, route: String, budget: Budget) -> Decision {
+    let now = Instant::now();
+    let rate = budget.per_second();
+    let capacity = budget.requests as f64;
+    let mut buckets = BUCKETS.lock().unwrap_or_else(|err| err.into_inner());
+    let bucket = buckets.entry((key, route)).or_insert(Bucket {
+        tokens: capacity,
+        updated: now,
+    });
+    bucket.tokens =
+        (bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * rate).min(capacity);
+    bucket.updated = now;
+    let allowed = bucket.tokens >= 1.0;
+    if allowed {
+        bucket.tokens -= 1.0;
+    }
+    Decision {
+        allowed,
+        remaining: bucket.tokens.floor() as u32,
+        retry_after: ((1.0 - bucket.tokens).max(0.0) / rate).ceil() as u64,
+        reset: ((capacity - bucket.tokens) / rate).ceil() as u64,
+    }
+}
+
+pub async fn enforce(ip: ClientIp, req: Request, next: Next) -> Response {
+    let (route, budget) = LIMITS.route(req.uri().path());
+    let route = route.unwrap_or("default").to_string();
+    let decision = take(client_key(req.headers(), &ip).await, route, budget);
+    let mut res = if decision.allowed {
+        next.run(req).await
+    } else {
+        let wait = decision.retry_after.max(1);
+        retry_resp(
+            wait as i64,
+            format!("Too many requests, try again in {} seconds.", wait),
+        )
+        .into_response()
+    };
+    let headers = res.headers_mut();
+    headers.insert("x-ratelimit-limit", HeaderValue::from(budget.requests));
+    headers.insert(
+        "x-ratelimit-remaining",
+        HeaderValue::from(decision.remaining),
+    );
+    headers.insert("x-ratelimit-reset", HeaderValue::from(decision.reset));
+    res
+}
+
+// Drops buckets that have refilled, they behave the same as new ones
+pub async fn run_bucket_cleanup() {
+    let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+    loop {
+        interval.tick().await;
+        let now = Instant::now();
+        let mut buckets = BUCKETS.lock().unwrap_or_else(|err| err.into_inner());
+        buckets.retain(|(_, route), bucket| {
+            let budget = match route.as_str() {
+                "default" => LIMITS.default,
+                route => LIMITS.routes.get(route).copied().unwrap_or(LIMITS.default),
+            };
+            let refilled = bucket.tokens
+                + now.duration_since(bucket.updated).as_secs_f64() * budget.per_second();
+            refilled < budget.requests as f64
+        });
+    }
+}
@@ -4,9 +4,16 @@
     Find the LICENSE file in the root of this repository for more details.
 */
 
-use crate::utils::db::Container;
+use crate::utils::{
+    db::{
+        ActiveSession, ApiToken, AuditEntry, Container, ContainerEvent, ContainerSchedule,
+        ContainerUsage, LoginEvent, OrgMember, OrgMembership, SignupInvite, UserDetails,
+    },
+    roles::Role,
+    signup_policy::SignupPolicy,
+};
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -20,16 +27,97 @@ pub struct MReturn {
 #[derive(Serialize)]
 #[serde(untagged)]
 pub enum GenericResponse {
-    Token { token: String },
-    Pre { name: String },
-    Credits { credits: i64 },
-    Container { id: String, port: u16 },
+    Token {
+        token: String,
+    },
+    Challenge {
+        mfa_required: bool,
+        challenge: String,
+        expires_in: i64,
+    },
+    TotpEnrollment {
+        uri: String,
+        secret: String,
+    },
+    RecoveryCodes {
+        recovery_codes: Vec<String>,
+    },
+    ApiToken {
+        id: i64,
+        token: String,
+    },
+    ApiTokens {
+        tokens: Vec<ApiToken>,
+    },
+    Session {
+        token: String,
+        refresh_token: String,
+        expires_in: i64,
+    },
+    Pre {
+        name: String,
+    },
+    Credits {
+        credits: i64,
+    },
+    Container {
+        id: String,
+        port: u16,
+    },
+    Secrets {
+        secrets: Vec<String>,
+    },
+    Schedules {
+        schedules: Vec<ContainerSchedule>,
+    },
+    Events {
+        events: Vec<ContainerEvent>,
+    },
+    Notifications {
+        email: bool,
+        webhook: Option<String>,
+    },
+    User {
+        user: Box<UserDetails>,
+        roles: Vec<Role>,
+    },
+    Orgs {
+        orgs: Vec<OrgMembership>,
+    },
+    OrgMembers {
+        members: Vec<OrgMember>,
+    },
+    SignupPolicy {
+        policy: SignupPolicy,
+        invites: Vec<SignupInvite>,
+    },
+    InviteCode {
+        id: i64,
+        code: String,
+    },
+    AuditLog {
+        entries: Vec<AuditEntry>,
+    },
+    Security {
+        logins: Vec<LoginEvent>,
+        sessions: Vec<ActiveSession>,
+    },
+    NetworkUsage {
+        period: String,
+        rx: i64,
+        tx: i64,
+        included_transfer: i64,
+        throttled: bool,
+        containers: Vec<ContainerUsage>,
+    },
 }
 
 pub enum Respond {
     Message(StatusCode, String),
     Containers(StatusCode, Vec<Container>),
     Generic(StatusCode, GenericResponse),
+    // 429 telling the client how many seconds to wait
+    RetryAfter(i64, String),
 }
 
 pub fn json_resp<T: Serialize>(status_code: StatusCode, body: T) -> Response {
@@ -42,6 +130,12 @@ impl IntoResponse for Respond {
             Respond::Message(status_code, message) => json_resp(status_code, MReturn { message }),
             Respond::Containers(status_code, containers) => json_resp(status_code, containers),
             Respond::Generic(status_code, response) => json_resp(status_code, response),
+            Respond::RetryAfter(seconds, message) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, seconds.to_string())],
+                Json(MReturn { message }),
+            )
+                .into_response(),
         }
     }
 }
@@ -50,6 +144,10 @@ pub fn m_resp(status_code: StatusCode, message: impl Into<String>) -> Respond {
     Respond::Message(status_code, message.into())
 }
 
+pub fn retry_resp(seconds: i64, message: impl Into<String>) -> Respond {
+    Respond::RetryAfter(seconds, message.into())
+}
+
 pub fn jwt_resp(status_code: StatusCode, token: String) -> Respond {
     Respond::Generic(status_code, GenericResponse::Token { token })
 }
@@ -61,3 +159,7 @@ pub fn pre_resp(name: String) -> Respond {
 pub fn credits_resp(credits: i64) -> Respond {
     Respond::Generic(StatusCode::OK, GenericResponse::Credits { credits })
 }
+
+pub fn secrets_resp(secrets: Vec<String>) -> Respond {
+    Respond::Generic(StatusCode::OK, GenericResponse::Secrets { secrets })
+}
@@ -5,25 +5,49 @@ use serde::Deserialize;
     Dockify is licensed under the Server Side Public License (SSPL), Version 1.
     Find the LICENSE file in the root of this repository for more details.
 */
+const GIB: i64 = 1024 * 1024 * 1024;
+// Bounds for a container's disk quota, in bytes
+pub const MIN_DISK: i64 = GIB;
+pub const MAX_DISK: i64 = 100 * GIB;
+
+pub fn default_disk() -> i64 {
+    10 * GIB
+}
+
+pub fn validate_disk(disk: i64) -> Result<(), String> {
+    if !(MIN_DISK..=MAX_DISK).contains(&disk) {
+        return Err(format!(
+            "disk must be between {} and {} GiB.",
+            MIN_DISK / GIB,
+            MAX_DISK / GIB
+        ));
+    }
+    Ok(())
+}
+
 #[derive(Deserialize)]
 pub struct ContainerResources {
     pub cpu_shares: i64,
     pub memory: i64,
     pub memory_swap: i64,
     pub cpu_cores: i64,
+    // Size limit of the container's writable layer, in bytes
+    #[serde(default = "default_disk")]
+    pub disk: i64,
 }
 
 impl ContainerResources {
-    pub fn new(memory: i64, memory_swap: i64, cpu_cores: i64, cpu_shares: i64) -> Self {
+    pub fn new(memory: i64, memory_swap: i64, cpu_cores: i64, cpu_shares: i64, disk: i64) -> Self {
         Self {
             memory,
             memory_swap,
             cpu_cores,
             cpu_shares,
+            disk,
         }
     }
     pub fn calculate_price(&self) -> i64 {
-        self.memory_cost() + self.swap_cost() + self.cpu_cost()
+        self.memory_cost() + self.swap_cost() + self.cpu_cost() + self.disk_cost()
     }
     fn memory_cost(&self) -> i64 {
         bytes_to_gigabytes(self.memory) * (2) + (2)
@@ -34,6 +58,10 @@ impl ContainerResources {
     fn cpu_cost(&self) -> i64 {
         self.cpu_cores * 15
     }
+    // Every started 5 GiB costs a credit
+    fn disk_cost(&self) -> i64 {
+        (self.disk.max(0) + 5 * GIB - 1) / (5 * GIB)
+    }
 }
 fn bytes_to_gigabytes(bytes: i64) -> i64 {
     bytes / (1024 * 1024 * 1024)
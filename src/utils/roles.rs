@@ -0,0 +1,159 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::marker::PhantomData;
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{
+    auth::User,
+    db,
+    res::{m_resp, Respond},
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    SupportAgent,
+    BillingAdmin,
+    SuperAdmin,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Permission {
+    ViewUsers,
+    ViewContainers,
+    ManageCredits,
+    ManagePlans,
+    ManageRoles,
+    ViewAuditLog,
+    ManageSignups,
+    UnlockAccounts,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::SupportAgent => "support_agent",
+            Role::BillingAdmin => "billing_admin",
+            Role::SuperAdmin => "super_admin",
+        }
+    }
+    pub fn parse(role: &str) -> Option<Role> {
+        match role {
+            "support_agent" => Some(Role::SupportAgent),
+            "billing_admin" => Some(Role::BillingAdmin),
+            "super_admin" => Some(Role::SuperAdmin),
+            _ => None,
+        }
+    }
+    pub fn grants(&self, permission: Permission) -> bool {
+        match self {
+            Role::SupportAgent => matches!(
+                permission,
+                Permission::ViewUsers | Permission::ViewContainers | Permission::UnlockAccounts
+            ),
+            Role::BillingAdmin => matches!(
+                permission,
+                Permission::ViewUsers | Permission::ManageCredits | Permission::ManagePlans
+            ),
+            Role::SuperAdmin => true,
+        }
+    }
+}
+
+// The legacy `admin` flag is turned into the super admin role when the database is migrated
+pub fn user_roles(username: &str) -> rusqlite::Result<Vec<Role>> {
+    Ok(db::get_user_roles(username)?
+        .iter()
+        .filter_map(|role| Role::parse(role))
+        .collect())
+}
+
+// Audit failures are logged but don't undo the action that was already taken
+pub fn audit(actor: &str, action: &str, target: &str, detail: Option<&str>) {
+    if let Err(err) = db::insert_audit_entry(actor, action, target, detail, Utc::now().timestamp())
+    {
+        eprintln!(
+            "An error occurred while writing audit entry {} by {} on {}: {}",
+            action, actor, target, err
+        );
+    }
+}
+
+pub trait Requirement {
+    const PERMISSION: Permission;
+}
+
+pub struct ViewUsers;
+impl Requirement for ViewUsers {
+    const PERMISSION: Permission = Permission::ViewUsers;
+}
+pub struct ViewContainers;
+impl Requirement for ViewContainers {
+    const PERMISSION: Permission = Permission::ViewContainers;
+}
+pub struct ManageCredits;
+impl Requirement for ManageCredits {
+    const PERMISSION: Permission = Permission::ManageCredits;
+}
+pub struct ManagePlans;
+impl Requirement for ManagePlans {
+    const PERMISSION: Permission = Permission::ManagePlans;
+}
+pub struct ManageRoles;
+impl Requirement for ManageRoles {
+    const PERMISSION: Permission = Permission::ManageRoles;
+}
+pub struct ViewAuditLog;
+impl Requirement for ViewAuditLog {
+    const PERMISSION: Permission = Permission::ViewAuditLog;
+}
+pub struct ManageSignups;
+impl Requirement for ManageSignups {
+    const PERMISSION: Permission = Permission::ManageSignups;
+}
+pub struct UnlockAccounts;
+impl Requirement for UnlockAccounts {
+    const PERMISSION: Permission = Permission::UnlockAccounts;
+}
+
+// Extractor for admin routes: yields the requesting user, rejecting the request unless
+// one of their roles grants `P::PERMISSION`. Super admin only routes use a permission
+// that only `Role::SuperAdmin` has, like `ManageRoles`.
+pub struct Authorized<P> {
+    pub username: String,
+    permission: PhantomData<P>,
+}
+
+#[async_trait]
+impl<P, S> FromRequestParts<S> for Authorized<P>
+where
+    P: Requirement,
+    S: Send + Sync,
+{
+    type Rejection = Respond;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let user = User::from_request_parts(parts, state).await?;
+        if !user.has_permission(P::PERMISSION) {
+            return Err(m_resp(
+                StatusCode::FORBIDDEN,
+                "User doesn't have permission to do this.",
+            ));
+        }
+        Ok(Authorized {
+            username: user.username,
+            permission: PhantomData,
+        })
+    }
+}
@@ -0,0 +1,185 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::{error::Error, str::FromStr, time::Duration};
+
+use bollard::Docker;
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{bandwidth, container, db};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Start,
+    Stop,
+    Restart,
+}
+
+impl Action {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Start => "start",
+            Action::Stop => "stop",
+            Action::Restart => "restart",
+        }
+    }
+    pub fn parse(action: &str) -> Option<Self> {
+        match action {
+            "start" => Some(Action::Start),
+            "stop" => Some(Action::Stop),
+            "restart" => Some(Action::Restart),
+            _ => None,
+        }
+    }
+}
+
+// Accepts standard five field expressions ("0 22 * * 1-5") evaluated in UTC
+pub fn parse_cron(expression: &str) -> Result<Schedule, String> {
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    if fields.len() != 5 {
+        return Err("Cron expressions must have 5 fields.".to_string());
+    }
+    let day_of_week = translate_day_of_week(fields[4])?;
+    Schedule::from_str(&format!("0 {} {}", fields[..4].join(" "), day_of_week))
+        .map_err(|err| err.to_string())
+}
+
+// Standard cron counts days from 0 (or 7) = Sunday, the cron crate from 1 = Sunday.
+// Numeric items are expanded into the crate's numbering, names are left as they are.
+fn translate_day_of_week(field: &str) -> Result<String, String> {
+    let mut items = Vec::new();
+    for item in field.split(',') {
+        if item.chars().any(|c| c.is_ascii_alphabetic()) || item == "*" || item == "?" {
+            items.push(item.to_string());
+            continue;
+        }
+        let invalid = || format!("Invalid day of week: {}", item);
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (item, 1),
+        };
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (start.parse().ok(), end.parse().ok()),
+            None if range == "*" => (Some(0), Some(6)),
+            None if step > 1 => (range.parse().ok(), Some(6)),
+            None => (range.parse().ok(), range.parse().ok()),
+        };
+        let (start, end): (u32, u32) = start.zip(end).ok_or_else(invalid)?;
+        if step == 0 || start > end || end > 7 {
+            return Err(invalid());
+        }
+        for day in (start..=end).step_by(step as usize) {
+            let day = (day % 7 + 1).to_string();
+            if !items.contains(&day) {
+                items.push(day);
+            }
+        }
+    }
+    Ok(items.join(","))
+}
+
+pub fn next_run(schedule: &Schedule, after: DateTime<Utc>) -> Option<i64> {
+    schedule.after(&after).next().map(|next| next.timestamp())
+}
+
+pub async fn run_scheduler() {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(err) = run_due().await {
+            eprintln!("Error while running scheduled actions: {}", err);
+        }
+    }
+}
+
+async fn run_due() -> Result<(), Box<dyn Error>> {
+    let now = Utc::now();
+    for due in db::get_due_schedules(now.timestamp())? {
+        println!(
+            "Running scheduled {} of '{}' for {}",
+            due.action, due.container, due.username
+        );
+        match Action::parse(&due.action) {
+            Some(action) => {
+                if let Err(err) = run_action(action, &due.container, &due.username).await {
+                    eprintln!(
+                        "Scheduled {} of '{}' failed: {}",
+                        due.action, due.container, err
+                    );
+                }
+            }
+            None => eprintln!("Unknown scheduled action: {}", due.action),
+        }
+        // Missed runs are skipped rather than replayed
+        let next = parse_cron(&due.cron)
+            .ok()
+            .and_then(|schedule| next_run(&schedule, now));
+        db::set_schedule_run(due.id, now.timestamp(), next)?;
+    }
+    Ok(())
+}
+
+async fn run_action(action: Action, name: &str, username: &str) -> Result<(), Box<dyn Error>> {
+    match action {
+        Action::Stop => container::stop_container(name).await?,
+        // A stopped container is simply started
+        Action::Restart => container::stop_container(name).await.unwrap_or(()),
+        Action::Start => (),
+    }
+    if action != Action::Stop {
+        container::start_container(name).await?;
+        db::set_sleeping(name, false)?;
+        let docker = Docker::connect_with_local_defaults()?;
+        bandwidth::apply_rate_limit(&docker, name, username).await;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, TimeZone, Weekday};
+
+    use super::*;
+
+    fn weekdays(expression: &str) -> Vec<Weekday> {
+        // 2024-01-01 is a Monday
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let schedule = parse_cron(expression).unwrap();
+        schedule
+            .after(&start)
+            .take(7)
+            .map(|next| next.weekday())
+            .collect()
+    }
+
+    #[test]
+    fn numeric_days_follow_standard_cron() {
+        use Weekday::*;
+        assert_eq!(
+            weekdays("0 12 * * 1-5"),
+            [Mon, Tue, Wed, Thu, Fri, Mon, Tue]
+        );
+        assert_eq!(weekdays("0 12 * * 0"), [Sun; 7]);
+        assert_eq!(weekdays("0 12 * * 7"), [Sun; 7]);
+        assert_eq!(
+            weekdays("0 12 * * 5-7"),
+            [Fri, Sat, Sun, Fri, Sat, Sun, Fri]
+        );
+        assert_eq!(weekdays("0 12 * * MON-FRI"), weekdays("0 12 * * 1-5"));
+    }
+
+    #[test]
+    fn rejects_invalid_days() {
+        assert!(parse_cron("0 12 * * 8").is_err());
+        assert!(parse_cron("0 12 * * 5-1").is_err());
+        assert!(parse_cron("0 12 * *").is_err());
+    }
+}
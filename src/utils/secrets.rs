@@ -0,0 +1,59 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use base64::{engine::general_purpose, Engine as _};
+use dotenvy::var;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+// Base64 encoded 32 byte key used to encrypt user secrets at rest
+static SECRETS_KEY: Lazy<Vec<u8>> = Lazy::new(|| {
+    let key = general_purpose::STANDARD
+        .decode(var("SECRETS_KEY").expect("Failed to retrieve SECRETS_KEY"))
+        .expect("SECRETS_KEY must be valid base64");
+    if key.len() != 32 {
+        panic!("SECRETS_KEY must decode to 32 bytes");
+    }
+    key
+});
+static NAME_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap());
+
+fn cipher() -> Aes256Gcm {
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&SECRETS_KEY))
+}
+
+// Returns the base64 encoded nonce and ciphertext
+pub fn encrypt(value: &str) -> Result<(String, String), aes_gcm::Error> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher().encrypt(&nonce, value.as_bytes())?;
+    Ok((
+        general_purpose::STANDARD.encode(nonce),
+        general_purpose::STANDARD.encode(ciphertext),
+    ))
+}
+
+pub fn decrypt(nonce: &str, ciphertext: &str) -> Result<String, aes_gcm::Error> {
+    let nonce = general_purpose::STANDARD
+        .decode(nonce)
+        .map_err(|_| aes_gcm::Error)?;
+    let ciphertext = general_purpose::STANDARD
+        .decode(ciphertext)
+        .map_err(|_| aes_gcm::Error)?;
+    if nonce.len() != 12 {
+        return Err(aes_gcm::Error);
+    }
+    let plaintext = cipher().decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())?;
+    String::from_utf8(plaintext).map_err(|_| aes_gcm::Error)
+}
+
+// Secret names and environment variable keys share the same rules
+pub fn validate_name(name: &str) -> bool {
+    name.len() <= 128 && NAME_REGEX.is_match(name)
+}
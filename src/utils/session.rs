@@ -0,0 +1,83 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::error::Error;
+
+use axum::http::StatusCode;
+use chrono::{Duration, Utc};
+
+use crate::utils::{
+    db::{self, Refresh},
+    res::{GenericResponse, Respond},
+    validation::{self, Claims},
+};
+
+pub const ACCESS_TOKEN_MINUTES: i64 = 15;
+const REFRESH_TOKEN_DAYS: i64 = 30;
+
+fn access_token(username: &str, session: &str) -> Result<String, jsonwebtoken::errors::Error> {
+    let now = Utc::now();
+    let claims = Claims {
+        sub: username.to_string(),
+        exp: (now + Duration::minutes(ACCESS_TOKEN_MINUTES)).timestamp() as usize,
+        iat: now.timestamp() as usize,
+        sid: Some(session.to_string()),
+        purpose: None,
+    };
+    validation::encode_claims(&claims)
+}
+
+fn session_resp(token: String, refresh_token: String) -> Respond {
+    Respond::Generic(
+        StatusCode::OK,
+        GenericResponse::Session {
+            token,
+            refresh_token,
+            expires_in: ACCESS_TOKEN_MINUTES * 60,
+        },
+    )
+}
+
+// Starts a new session and returns it as a JSON response
+pub fn create(username: &str) -> Result<Respond, Box<dyn Error>> {
+    let (token, refresh_token) = start(username)?;
+    Ok(session_resp(token, refresh_token))
+}
+
+// Starts a new session and returns its access and refresh tokens
+pub fn start(username: &str) -> Result<(String, String), Box<dyn Error>> {
+    let id = validation::generate_token();
+    let refresh_token = validation::generate_token();
+    let now = Utc::now();
+    db::insert_session(
+        &id,
+        username,
+        &validation::hash_token(&refresh_token),
+        now.timestamp(),
+        (now + Duration::days(REFRESH_TOKEN_DAYS)).timestamp(),
+    )?;
+    Ok((access_token(username, &id)?, refresh_token))
+}
+
+// Exchanges a refresh token for a new pair, each refresh token works once
+pub fn refresh(refresh_token: &str) -> Result<Option<Respond>, Box<dyn Error>> {
+    let new_refresh_token = validation::generate_token();
+    match db::rotate_session(
+        &validation::hash_token(refresh_token),
+        &validation::hash_token(&new_refresh_token),
+        Utc::now().timestamp(),
+    )? {
+        Refresh::Rotated(id, username) => Ok(Some(session_resp(
+            access_token(&username, &id)?,
+            new_refresh_token,
+        ))),
+        Refresh::Reused => {
+            println!("A rotated refresh token was reused, revoked its session.");
+            Ok(None)
+        }
+        Refresh::Invalid => Ok(None),
+    }
+}
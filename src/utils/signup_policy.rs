@@ -0,0 +1,137 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::{collections::HashSet, fs, sync::RwLock, time::SystemTime};
+
+use axum::http::StatusCode;
+use dotenvy::var;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{db, validation};
+
+const SETTING_KEY: &str = "signup_policy";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SignupPolicy {
+    // Only these domains may sign up, an empty list allows any domain
+    pub allowed_domains: Vec<String>,
+    // Blocked along with their subdomains
+    pub denied_domains: Vec<String>,
+    // New accounts need an invite code from an admin
+    pub invite_only: bool,
+}
+
+impl Default for SignupPolicy {
+    fn default() -> Self {
+        SignupPolicy {
+            allowed_domains: vec![
+                "gmail.com".to_string(),
+                "outlook.com".to_string(),
+                "sigma.town".to_string(),
+            ],
+            denied_domains: Vec::new(),
+            invite_only: false,
+        }
+    }
+}
+
+pub enum Rejection {
+    DomainNotAllowed,
+    InviteRequired,
+    Internal,
+}
+
+impl Rejection {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Rejection::DomainNotAllowed | Rejection::InviteRequired => StatusCode::FORBIDDEN,
+            Rejection::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+    pub fn message(&self) -> &'static str {
+        match self {
+            Rejection::DomainNotAllowed => "Signups from this email domain are not allowed.",
+            Rejection::InviteRequired => {
+                "Signups are invite only, please provide a valid invite code."
+            }
+            Rejection::Internal => "Please contact support for help.",
+        }
+    }
+}
+
+pub fn get() -> rusqlite::Result<SignupPolicy> {
+    Ok(db::get_setting(SETTING_KEY)?.unwrap_or_default())
+}
+pub fn set(policy: &SignupPolicy) -> rusqlite::Result<()> {
+    db::set_setting(SETTING_KEY, policy)
+}
+
+// One domain per line from DISPOSABLE_EMAIL_FILE, reloaded when the file changes
+static DISPOSABLE: Lazy<RwLock<(Option<SystemTime>, HashSet<String>)>> =
+    Lazy::new(|| RwLock::new((None, HashSet::new())));
+
+fn is_disposable(domain: &str) -> bool {
+    let Ok(path) = var("DISPOSABLE_EMAIL_FILE") else {
+        return false;
+    };
+    let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+    let stale = DISPOSABLE
+        .read()
+        .map(|cache| cache.0 != modified)
+        .unwrap_or(true);
+    if stale {
+        match fs::read_to_string(&path) {
+            Ok(contents) => {
+                let domains = contents
+                    .lines()
+                    .map(|line| line.trim().to_lowercase())
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .collect();
+                if let Ok(mut cache) = DISPOSABLE.write() {
+                    *cache = (modified, domains);
+                }
+            }
+            Err(err) => eprintln!("Failed to read DISPOSABLE_EMAIL_FILE: {}", err),
+        }
+    }
+    match DISPOSABLE.read() {
+        Ok(cache) => matches_domain(domain, cache.1.iter()),
+        Err(_) => false,
+    }
+}
+
+fn matches_domain<'a>(domain: &str, mut list: impl Iterator<Item = &'a String>) -> bool {
+    list.any(|entry| domain == entry || domain.ends_with(&format!(".{}", entry)))
+}
+
+pub fn check_email(policy: &SignupPolicy, email: &str) -> Result<(), Rejection> {
+    let domain = email.rsplit('@').next().unwrap_or("").to_lowercase();
+    if !policy.allowed_domains.is_empty() && !policy.allowed_domains.contains(&domain) {
+        return Err(Rejection::DomainNotAllowed);
+    }
+    if matches_domain(&domain, policy.denied_domains.iter()) || is_disposable(&domain) {
+        return Err(Rejection::DomainNotAllowed);
+    }
+    Ok(())
+}
+
+// Checks the email against the policy. Returns the hash of the invite code to redeem
+// when one is required, which db::insert_user_with_invite does along with the insert.
+pub fn admit(email: &str, invite_code: Option<&str>) -> Result<Option<String>, Rejection> {
+    let policy = get().map_err(|err| {
+        eprintln!("Error while getting signup policy: {}", err);
+        Rejection::Internal
+    })?;
+    check_email(&policy, email)?;
+    if !policy.invite_only {
+        return Ok(None);
+    }
+    let code = invite_code
+        .filter(|code| !code.is_empty())
+        .ok_or(Rejection::InviteRequired)?;
+    Ok(Some(validation::hash_token(code)))
+}
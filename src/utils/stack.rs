@@ -0,0 +1,416 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::collections::{BTreeMap, HashMap};
+
+use bollard::{
+    container::{
+        Config, CreateContainerOptions, NetworkingConfig, RemoveContainerOptions,
+        StartContainerOptions,
+    },
+    network::CreateNetworkOptions,
+    secret::EndpointSettings,
+    volume::RemoveVolumeOptions,
+    Docker,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{
+    bandwidth,
+    container::{self, create_config, resolve_env, RuntimeOptions},
+    db, hardening, network,
+    res::Respond,
+    resources::{default_disk, validate_disk, ContainerResources},
+    storage,
+};
+
+const MAX_VOLUMES: usize = 8;
+static SERVICE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[a-z0-9][a-z0-9_-]{0,31}$").unwrap());
+static VOLUME_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[A-Za-z0-9][A-Za-z0-9_.-]{0,63}$").unwrap());
+
+fn default_shares() -> i64 {
+    512
+}
+
+// A restricted subset of the compose file format, unknown keys are rejected
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct StackSpec {
+    pub services: BTreeMap<String, ServiceSpec>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServiceSpec {
+    pub image: String,
+    pub memory: i64,
+    pub memory_swap: i64,
+    pub cpu_cores: i64,
+    #[serde(default = "default_shares")]
+    pub cpu_shares: i64,
+    #[serde(default = "default_disk")]
+    pub disk: i64,
+    #[serde(default)]
+    pub environment: HashMap<String, String>,
+    #[serde(default)]
+    pub secrets: HashMap<String, String>,
+    // Container port published on a host port picked by Dockify
+    pub port: Option<u16>,
+    // Named volumes only, in the form name:/path[:ro]
+    #[serde(default)]
+    pub volumes: Vec<String>,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    pub command: Option<Vec<String>>,
+}
+
+impl ServiceSpec {
+    pub fn resources(&self) -> ContainerResources {
+        ContainerResources::new(
+            self.memory,
+            self.memory_swap,
+            self.cpu_cores,
+            self.cpu_shares,
+            self.disk,
+        )
+    }
+}
+
+impl StackSpec {
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        // JSON documents are valid YAML, so both formats go through serde_norway
+        serde_norway::from_slice::<StackSpec>(bytes).map_err(|err| err.to_string())
+    }
+
+    // Validates the spec and returns the service names in start order
+    pub fn validate(&self) -> Result<Vec<String>, String> {
+        // Every service is a container, so the container limit caps the stack size
+        let max_services = container::MAX_CONTAINERS as usize;
+        if self.services.is_empty() || self.services.len() > max_services {
+            return Err(format!(
+                "Stacks must have between 1 and {} services.",
+                max_services
+            ));
+        }
+        for (name, service) in &self.services {
+            if !SERVICE_REGEX.is_match(name) {
+                return Err(format!("Invalid service name: {}", name));
+            }
+            if service.image.is_empty() {
+                return Err(format!("Service {} has no image.", name));
+            }
+            if let Err(err) = validate_disk(service.disk) {
+                return Err(format!("Service {}: {}", name, err));
+            }
+            if service.volumes.len() > MAX_VOLUMES {
+                return Err(format!("Service {} has too many volumes.", name));
+            }
+            for volume in &service.volumes {
+                if parse_volume(volume).is_none() {
+                    return Err(format!("Invalid volume for service {}: {}", name, volume));
+                }
+            }
+            for dependency in &service.depends_on {
+                if !self.services.contains_key(dependency) || dependency == name {
+                    return Err(format!(
+                        "Service {} depends on unknown service {}.",
+                        name, dependency
+                    ));
+                }
+            }
+            RuntimeOptions {
+                cmd: service.command.clone(),
+                ..Default::default()
+            }
+            .validate()?;
+        }
+        self.start_order()
+    }
+
+    fn start_order(&self) -> Result<Vec<String>, String> {
+        let mut order: Vec<String> = Vec::new();
+        while order.len() < self.services.len() {
+            let next = self.services.iter().find(|(name, service)| {
+                !order.contains(name)
+                    && service
+                        .depends_on
+                        .iter()
+                        .all(|dependency| order.contains(dependency))
+            });
+            match next {
+                Some((name, _)) => order.push(name.clone()),
+                None => return Err("Service dependencies contain a cycle.".to_string()),
+            }
+        }
+        Ok(order)
+    }
+
+    pub fn total_resources(&self) -> ContainerResources {
+        let mut total = ContainerResources::new(0, 0, 0, 0, 0);
+        for service in self.services.values() {
+            total.memory += service.memory;
+            total.memory_swap += service.memory_swap;
+            total.cpu_cores += service.cpu_cores;
+            total.cpu_shares += service.cpu_shares;
+            total.disk += service.disk;
+        }
+        total
+    }
+}
+
+// Returns the volume name, mount path and whether it is read only
+fn parse_volume(volume: &str) -> Option<(&str, &str, bool)> {
+    let parts: Vec<&str> = volume.split(':').collect();
+    let read_only = match parts.get(2) {
+        None => false,
+        Some(&"ro") => true,
+        Some(&"rw") => false,
+        Some(_) => return None,
+    };
+    if parts.len() > 3 || parts.len() < 2 {
+        return None;
+    }
+    if !VOLUME_REGEX.is_match(parts[0]) || !parts[1].starts_with('/') {
+        return None;
+    }
+    Some((parts[0], parts[1], read_only))
+}
+
+pub fn network_name(stack: &str) -> String {
+    format!("dockify-stack-{}", stack)
+}
+pub fn container_name(stack: &str, service: &str) -> String {
+    format!("{}_{}", stack, service)
+}
+fn volume_name(stack: &str, volume: &str) -> String {
+    format!("{}_{}", stack, volume)
+}
+
+// Resolves each service's environment up front so errors reach the user
+pub fn resolve_stack_env(
+    username: &str,
+    spec: &StackSpec,
+) -> Result<HashMap<String, Vec<String>>, Respond> {
+    let mut envs = HashMap::new();
+    for (name, service) in &spec.services {
+        envs.insert(
+            name.clone(),
+            resolve_env(username, &service.environment, &service.secrets)?,
+        );
+    }
+    Ok(envs)
+}
+
+pub async fn create_stack(
+    spec: StackSpec,
+    order: Vec<String>,
+    mut envs: HashMap<String, Vec<String>>,
+    stack: String,
+    username: String,
+) {
+    let docker = match Docker::connect_with_local_defaults() {
+        Ok(docker) => docker,
+        Err(err) => {
+            eprintln!("Error connecting to Docker: {}", err);
+            return;
+        }
+    };
+    let network = network_name(&stack);
+    if let Err(err) = docker
+        .create_network(CreateNetworkOptions {
+            name: network.clone(),
+            driver: "bridge".to_string(),
+            labels: HashMap::from([("dockify.stack".to_string(), stack.clone())]),
+            ..Default::default()
+        })
+        .await
+    {
+        eprintln!("Error creating stack network: {}", err);
+        return;
+    }
+    let user_network = match network::ensure_user_network(&docker, &username).await {
+        Ok(network) => network,
+        Err(err) => {
+            eprintln!("Error creating user network: {}", err);
+            remove_stack_resources(&docker, &stack, &[]).await;
+            return;
+        }
+    };
+    let spec_json = serde_json::to_string(&spec).unwrap_or_default();
+    if let Err(err) = db::insert_stack(&stack, &username, &network, &spec_json) {
+        eprintln!("Error inserting stack into DB: {}", err);
+        remove_stack_resources(&docker, &stack, &[]).await;
+        return;
+    }
+
+    let profile = hardening::profile_for_user(&username);
+    let mut taken_ports: Vec<u16> = Vec::new();
+    let mut created: Vec<String> = Vec::new();
+    let mut failed = false;
+    for service_name in &order {
+        let service = &spec.services[service_name];
+        let name = container_name(&stack, service_name);
+        let mut ports: HashMap<u16, u16> = HashMap::new();
+        if let Some(port) = service.port {
+            match container::get_available_port_excluding(&taken_ports) {
+                Some(host_port) => {
+                    taken_ports.push(host_port);
+                    ports.insert(port, host_port);
+                }
+                None => {
+                    eprintln!("All ports are being used!");
+                    failed = true;
+                    break;
+                }
+            }
+        }
+        let mut config: Config<String> = create_config(
+            ports.clone(),
+            service.resources(),
+            service.image.clone(),
+            envs.remove(service_name).unwrap_or_default(),
+            RuntimeOptions {
+                cmd: service.command.clone(),
+                labels: Some(HashMap::from([(
+                    "dockify.stack".to_string(),
+                    stack.clone(),
+                )])),
+                ..Default::default()
+            },
+            &profile,
+        );
+        if let Some(host_config) = config.host_config.as_mut() {
+            host_config.network_mode = Some(network.clone());
+            storage::apply_quota(&docker, host_config, service.disk).await;
+            host_config.binds = Some(
+                service
+                    .volumes
+                    .iter()
+                    .filter_map(|volume| parse_volume(volume))
+                    .map(|(volume, path, read_only)| {
+                        format!(
+                            "{}:{}{}",
+                            volume_name(&stack, volume),
+                            path,
+                            if read_only { ":ro" } else { "" }
+                        )
+                    })
+                    .collect(),
+            );
+        }
+        config.networking_config = Some(NetworkingConfig {
+            endpoints_config: HashMap::from([(
+                network.clone(),
+                EndpointSettings {
+                    aliases: Some(vec![service_name.clone()]),
+                    ..Default::default()
+                },
+            )]),
+        });
+
+        let container = match docker
+            .create_container(
+                Some(CreateContainerOptions {
+                    name: name.clone(),
+                    platform: None,
+                }),
+                config.clone(),
+            )
+            .await
+        {
+            Ok(container) => container,
+            Err(err) => {
+                eprintln!("Error creating stack container {}: {}", name, err);
+                failed = true;
+                break;
+            }
+        };
+        created.push(name.clone());
+        // Also reachable by name from the user's standalone containers
+        if let Err(err) = network::connect_to_user_network(&docker, &user_network, &name).await {
+            eprintln!("Error connecting {} to user network: {}", name, err);
+            failed = true;
+            break;
+        }
+        if let Err(err) = docker
+            .start_container(&container.id, None::<StartContainerOptions<String>>)
+            .await
+        {
+            eprintln!("Error starting stack container {}: {}", name, err);
+            failed = true;
+            break;
+        }
+        bandwidth::apply_rate_limit(&docker, &name, &username).await;
+        let port = ports.values().next().copied().unwrap_or(0);
+        match db::insert_container(
+            &container.id,
+            username.clone(),
+            name.clone(),
+            config,
+            port,
+            Some(&stack),
+            service.disk,
+        )
+        .await
+        {
+            Ok(updated) if updated > 0 => (),
+            _ => {
+                eprintln!("Failed while inserting stack container into DB.");
+                failed = true;
+                break;
+            }
+        }
+    }
+    if failed {
+        remove_stack_resources(&docker, &stack, &created).await;
+        if let Err(err) = db::delete_stack(&stack) {
+            eprintln!("Error removing failed stack from DB: {}", err);
+        }
+        network::remove_user_network_if_unused(&docker, &username).await;
+        return;
+    }
+    println!("Stack '{}' created successfully.", stack);
+}
+
+// Removes the stack's containers, volumes and network, ignoring missing ones
+pub async fn remove_stack_resources(docker: &Docker, stack: &str, containers: &[String]) {
+    for name in containers.iter().rev() {
+        if let Err(err) = docker
+            .remove_container(
+                name,
+                Some(RemoveContainerOptions {
+                    force: true,
+                    v: true,
+                    ..Default::default()
+                }),
+            )
+            .await
+        {
+            eprintln!("Error removing stack container {}: {}", name, err);
+        }
+    }
+    if let Ok(spec) = db::get_stack_spec(stack) {
+        if let Ok(spec) = serde_json::from_str::<StackSpec>(&spec) {
+            for service in spec.services.values() {
+                for (volume, _, _) in service.volumes.iter().filter_map(|v| parse_volume(v)) {
+                    let _ = docker
+                        .remove_volume(
+                            &volume_name(stack, volume),
+                            Some(RemoveVolumeOptions { force: true }),
+                        )
+                        .await;
+                }
+            }
+        }
+    }
+    if let Err(err) = docker.remove_network(&network_name(stack)).await {
+        eprintln!("Error removing stack network: {}", err);
+    }
+}
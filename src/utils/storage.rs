@@ -0,0 +1,129 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::{collections::HashMap, time::Duration};
+
+use bollard::{container::ListContainersOptions, secret::HostConfig, Docker};
+use dotenvy::var;
+use once_cell::sync::Lazy;
+use tokio::sync::OnceCell;
+
+use crate::utils::{container, db, health};
+
+static QUOTA_SUPPORTED: OnceCell<bool> = OnceCell::const_new();
+static CHECK_INTERVAL: Lazy<u64> = Lazy::new(|| {
+    var("DISK_CHECK_INTERVAL")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(300)
+});
+// Percentage of the quota at which users are warned before being stopped
+const WARN_PERCENT: i64 = 90;
+
+// storage_opt size is only honoured by some storage drivers, overlay2 needs an
+// xfs backing filesystem mounted with pquota
+async fn quota_supported(docker: &Docker) -> bool {
+    *QUOTA_SUPPORTED
+        .get_or_init(|| async {
+            let info = match docker.info().await {
+                Ok(info) => info,
+                Err(err) => {
+                    eprintln!("Error getting Docker info: {}", err);
+                    return false;
+                }
+            };
+            let backing_fs = info
+                .driver_status
+                .unwrap_or_default()
+                .into_iter()
+                .find(|status| status.first().map(String::as_str) == Some("Backing Filesystem"))
+                .and_then(|status| status.get(1).cloned());
+            let supported = match info.driver.as_deref() {
+                Some("devicemapper") | Some("btrfs") | Some("zfs") | Some("windowsfilter") => true,
+                Some("overlay2") => backing_fs.as_deref() == Some("xfs"),
+                _ => false,
+            };
+            if !supported {
+                println!("Storage driver does not support disk quotas, relying on the checker.");
+            }
+            supported
+        })
+        .await
+}
+
+pub async fn apply_quota(docker: &Docker, host_config: &mut HostConfig, disk: i64) {
+    if disk > 0 && quota_supported(docker).await {
+        host_config.storage_opt = Some(HashMap::from([("size".to_string(), disk.to_string())]));
+    }
+}
+
+pub async fn run_quota_checker() {
+    let mut interval = tokio::time::interval(Duration::from_secs(*CHECK_INTERVAL));
+    loop {
+        interval.tick().await;
+        if let Err(err) = check_quotas().await {
+            eprintln!("Error while checking disk quotas: {}", err);
+        }
+    }
+}
+
+async fn check_quotas() -> Result<(), Box<dyn std::error::Error>> {
+    let docker = Docker::connect_with_local_defaults()?;
+    // Container id -> (writable layer size, running)
+    let sizes: HashMap<String, (i64, bool)> = docker
+        .list_containers(Some(ListContainersOptions::<String> {
+            all: true,
+            size: true,
+            ..Default::default()
+        }))
+        .await?
+        .into_iter()
+        .filter_map(|container| {
+            let running = container.state.as_deref() == Some("running");
+            Some((container.id?, (container.size_rw?, running)))
+        })
+        .collect();
+    for quota in db::get_disk_quotas()? {
+        let (used, running) = match sizes.get(&quota.id) {
+            Some(size) => *size,
+            None => continue,
+        };
+        if used > quota.disk {
+            if !running {
+                continue;
+            }
+            if let Err(err) = container::stop_container(&quota.name).await {
+                eprintln!("Error stopping container over disk quota: {}", err);
+                continue;
+            }
+            health::notify(
+                &quota.username,
+                &quota.name,
+                "over its disk quota",
+                &format!(
+                    "was stopped after using {} of its {} bytes of disk",
+                    used, quota.disk
+                ),
+            )
+            .await;
+        } else if used * 100 >= quota.disk * WARN_PERCENT && !quota.warned {
+            db::set_disk_warned(&quota.id, true)?;
+            health::notify(
+                &quota.username,
+                &quota.name,
+                "near its disk quota",
+                &format!(
+                    "is using {} of its {} bytes of disk and will be stopped when it runs out",
+                    used, quota.disk
+                ),
+            )
+            .await;
+        } else if used * 100 < quota.disk * WARN_PERCENT && quota.warned {
+            db::set_disk_warned(&quota.id, false)?;
+        }
+    }
+    Ok(())
+}
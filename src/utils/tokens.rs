@@ -0,0 +1,104 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use axum::{
+    extract::Request,
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{db, res::m_resp, validation};
+
+// Personal access tokens are told apart from JWTs by this prefix
+pub const PREFIX: &str = "dkf_";
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum Scope {
+    #[serde(rename = "read")]
+    Read,
+    #[serde(rename = "containers:write")]
+    ContainersWrite,
+    #[serde(rename = "billing:read")]
+    BillingRead,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::ContainersWrite => "containers:write",
+            Scope::BillingRead => "billing:read",
+        }
+    }
+}
+
+// Routes a token may call, anything not listed (account, admin and token
+// management) needs a login session
+fn required_scope(path: &str) -> Option<Scope> {
+    match path {
+        "/api/get_containers"
+        | "/api/get_secrets"
+        | "/api/get_schedules"
+        | "/api/events"
+        | "/api/events/ws"
+        | "/api/get_notifications" => Some(Scope::Read),
+        "/api/create_new_container"
+        | "/api/delete_container"
+        | "/api/start_container"
+        | "/api/recreate_container"
+        | "/api/stop_container"
+        | "/api/create_stack"
+        | "/api/delete_stack"
+        | "/api/start_stack"
+        | "/api/stop_stack"
+        | "/api/create_schedule"
+        | "/api/delete_schedule"
+        | "/api/set_secret"
+        | "/api/delete_secret" => Some(Scope::ContainersWrite),
+        "/api/get_credits" | "/api/get_network_usage" => Some(Scope::BillingRead),
+        _ => None,
+    }
+}
+
+pub fn is_token(token: &str) -> bool {
+    token.starts_with(PREFIX)
+}
+
+pub fn generate() -> String {
+    format!("{}{}", PREFIX, validation::generate_token())
+}
+
+// Rejects requests made with a personal access token that lacks the route's scope
+pub async fn enforce_scopes(req: Request, next: Next) -> Response {
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(' ').nth(1))
+        .filter(|token| is_token(token));
+    let Some(token) = token else {
+        return next.run(req).await;
+    };
+    let scopes = match db::get_api_token_scopes(&validation::hash_token(token)) {
+        Ok(Some(scopes)) => scopes,
+        // Unknown tokens are rejected by the handler itself
+        Ok(None) => return next.run(req).await,
+        Err(err) => {
+            eprintln!("Error while getting token scopes: {}", err);
+            return m_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Please contact support for help.",
+            )
+            .into_response();
+        }
+    };
+    match required_scope(req.uri().path()) {
+        Some(scope) if scopes.iter().any(|s| s == scope.as_str()) => next.run(req).await,
+        _ => m_resp(StatusCode::FORBIDDEN, "Token lacks the required scope").into_response(),
+    }
+}
@@ -13,8 +13,12 @@ use chrono::{DateTime, Utc};
 use dotenvy::var;
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use once_cell::sync::Lazy;
+use rand::distributions::{Alphanumeric, DistString};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::utils::{db, tokens};
 
 static JWT_KEY: Lazy<String> = Lazy::new(|| var("JWT_KEY").expect("Failed to retrieve JWT_KEY"));
 
@@ -22,6 +26,12 @@ static JWT_KEY: Lazy<String> = Lazy::new(|| var("JWT_KEY").expect("Failed to ret
 pub struct Claims {
     pub sub: String, // Subject, can be a user identifier
     pub exp: usize,  // Expiration time as a timestamp
+    #[serde(default)]
+    pub iat: usize, // Issued at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>, // Session of an access token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>, // Set on tokens that are not for authentication
 }
 
 pub fn generate_jwt(
@@ -31,11 +41,17 @@ pub fn generate_jwt(
     let claims = Claims {
         sub: key.into().to_owned(), // or whatever you want to use as the subject
         exp: exp.timestamp() as usize,
+        iat: Utc::now().timestamp() as usize,
+        sid: None,
+        purpose: None,
     };
 
+    encode_claims(&claims)
+}
+pub fn encode_claims(claims: &Claims) -> Result<String, jsonwebtoken::errors::Error> {
     encode(
         &Header::default(),
-        &claims,
+        claims,
         &EncodingKey::from_secret(&JWT_KEY.clone().into_bytes()),
     )
 }
@@ -70,10 +86,42 @@ pub fn verify_password(
 }
 
 pub async fn validate_token(token: impl Into<String>) -> (bool, String) {
-    match verify_jwt(&token.into()).await {
-        Ok(claims) => (true, claims.sub),
-        Err(_) => (false, "".to_string()),
+    let token = token.into();
+    // Personal access tokens, their scopes are checked by tokens::enforce_scopes
+    if tokens::is_token(&token) {
+        return match db::use_api_token(&hash_token(&token), Utc::now().timestamp()) {
+            Ok(Some(username)) => (true, username),
+            Ok(None) => (false, "".to_string()),
+            Err(err) => {
+                eprintln!("Error while checking access token: {}", err);
+                (false, "".to_string())
+            }
+        };
     }
+    let claims = match verify_jwt(&token).await {
+        Ok(claims) => claims,
+        Err(_) => return (false, "".to_string()),
+    };
+    // Only access tokens carry a session, other JWTs like verification codes are rejected
+    let Some(sid) = claims.sid else {
+        return (false, "".to_string());
+    };
+    match db::is_session_active(&sid, &claims.sub, Utc::now().timestamp()) {
+        Ok(true) => (true, claims.sub),
+        Ok(false) => (false, "".to_string()),
+        Err(err) => {
+            eprintln!("Error while checking user session: {}", err);
+            (false, "".to_string())
+        }
+    }
+}
+
+// Random single-use token, only its hash is ever stored
+pub fn generate_token() -> String {
+    Alphanumeric.sample_string(&mut rand::thread_rng(), 48)
+}
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
 }
 
 pub async fn validate_request(headers: &HeaderMap) -> (bool, String) {
@@ -94,24 +142,25 @@ pub async fn validate_request(headers: &HeaderMap) -> (bool, String) {
 
     validate_token(parsed_token).await
 }
+// Session of an already validated request
+pub async fn request_session(headers: &HeaderMap) -> Option<String> {
+    let auth_str = headers
+        .get(axum::http::header::AUTHORIZATION)?
+        .to_str()
+        .ok()?;
+    verify_jwt(auth_str.split(' ').nth(1)?).await.ok()?.sid
+}
+// Usernames are compared lowercased and never contain ':', which org accounts use
+pub fn is_username_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+pub const MAX_USERNAME_LEN: usize = 32;
+pub fn validate_username(username: &str) -> bool {
+    (1..=MAX_USERNAME_LEN).contains(&username.len()) && username.chars().all(is_username_char)
+}
 static EMAIL_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^[\w\.-]+@[a-zA-Z\d\.-]+\.[a-zA-Z]{2,}$").unwrap());
-static ALLOWED_EMAIL_DOMAINS: Lazy<Vec<&str>> =
-    Lazy::new(|| ["gmail.com", "outlook.com", "sigma.town"].to_vec());
+// Format only, signup additionally applies the signup policy
 pub fn validate_email(email: &str) -> bool {
-    if !&email.is_ascii() || !EMAIL_REGEX.is_match(&email) || !is_domain_accepted(email) {
-        false
-    } else {
-        true
-    }
-}
-fn is_domain_accepted(email: &str) -> bool {
-    let parts: Vec<&str> = email.split('@').collect();
-    if parts.len() != 2 {
-        return false;
-    }
-
-    let domain = parts[1];
-
-    ALLOWED_EMAIL_DOMAINS.contains(&domain)
+    email.is_ascii() && EMAIL_REGEX.is_match(email)
 }
@@ -0,0 +1,53 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::{error::Error, time::Duration};
+
+use chrono::Utc;
+use dotenvy::var;
+use once_cell::sync::Lazy;
+
+use crate::utils::{db, lockout, login_log};
+
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// Unverified accounts are deleted after this many days, freeing the username and email
+static UNVERIFIED_ACCOUNT_DAYS: Lazy<i64> = Lazy::new(|| {
+    var("UNVERIFIED_ACCOUNT_DAYS")
+        .ok()
+        .and_then(|days| days.parse().ok())
+        .unwrap_or(7)
+});
+
+pub async fn run_verification_cleanup() {
+    let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(err) = cleanup() {
+            eprintln!("Error while cleaning up verifications: {}", err);
+        }
+    }
+}
+
+fn cleanup() -> Result<(), Box<dyn Error>> {
+    let now = Utc::now().timestamp();
+    let purged = db::purge_expired_codes(now)?;
+    if purged > 0 {
+        println!("Purged {} expired verification codes.", purged);
+    }
+    let purged = lockout::purge_expired()?;
+    if purged > 0 {
+        println!("Purged {} expired login failure counters.", purged);
+    }
+    let purged = login_log::purge_expired()?;
+    if purged > 0 {
+        println!("Purged {} old login events.", purged);
+    }
+    let cutoff = now - *UNVERIFIED_ACCOUNT_DAYS * 24 * 60 * 60;
+    for username in db::delete_unverified_users(cutoff)? {
+        println!("Deleted unverified account {}.", username);
+    }
+    Ok(())
+}
@@ -0,0 +1,92 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+use std::{
+    error::Error,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+
+use reqwest::{redirect::Policy, Url};
+use serde_json::Value;
+
+const MAX_WEBHOOK_LEN: usize = 2048;
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    // 100.64.0.0/10 is carrier-grade NAT space
+    let shared = a == 100 && (64..128).contains(&b);
+    !(ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || shared)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    if let Some(ip) = ip.to_ipv4_mapped() {
+        return is_public_v4(ip);
+    }
+    let first = ip.segments()[0];
+    let unique_local = first & 0xfe00 == 0xfc00;
+    let link_local = first & 0xffc0 == 0xfe80;
+    !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || unique_local || link_local)
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => is_public_v6(ip),
+    }
+}
+
+// Webhooks must be https and may only reach public addresses, so they can't be used to
+// probe the host or its internal network. Returns the address the request should go to.
+pub async fn resolve(webhook: &str) -> Result<(Url, SocketAddr), String> {
+    let invalid = || "Webhook must be an https URL.".to_string();
+    if webhook.len() > MAX_WEBHOOK_LEN {
+        return Err(invalid());
+    }
+    let url = Url::parse(webhook).map_err(|_| invalid())?;
+    if url.scheme() != "https" {
+        return Err(invalid());
+    }
+    let port = url.port_or_known_default().unwrap_or(443);
+    let host = url.host_str().ok_or_else(invalid)?;
+    let addrs: Vec<SocketAddr> = match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|_| "Webhook host could not be resolved.".to_string())?
+            .collect(),
+    };
+    // Every address must be public, as any of them might be used
+    if addrs.is_empty() || addrs.iter().any(|addr| !is_public(addr.ip())) {
+        return Err("Webhook must point to a public address.".to_string());
+    }
+    Ok((url, addrs[0]))
+}
+
+pub async fn post(webhook: &str, payload: &Value) -> Result<(), Box<dyn Error>> {
+    let (url, addr) = resolve(webhook).await?;
+    let mut client = reqwest::Client::builder()
+        .redirect(Policy::none())
+        .timeout(Duration::from_secs(10));
+    // Pinning the checked address stops the host from resolving elsewhere for the request
+    if let Some(host) = url.host_str() {
+        client = client.resolve(host, addr);
+    }
+    client
+        .build()?
+        .post(url)
+        .json(payload)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
@@ -0,0 +1,349 @@
+/*
+    This source file is a part of Dockify
+    Dockify is licensed under the Server Side Public License (SSPL), Version 1.
+    Find the LICENSE file in the root of this repository for more details.
+*/
+
+// Runs the OIDC login against a mock provider on 127.0.0.1
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Form, Json, Router,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use dockify_backend::utils::{
+    db,
+    oidc::{self, OidcConfig},
+    validation,
+};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use rsa::{pkcs1::EncodeRsaPrivateKey, traits::PublicKeyParts, RsaPrivateKey};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tokio::net::TcpListener;
+
+const CLIENT_ID: &str = "dockify";
+const CLIENT_SECRET: &str = "secret";
+const REDIRECT_URI: &str = "http://127.0.0.1/api/oidc/callback";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Tamper {
+    None,
+    Nonce,
+    Audience,
+    Key,
+    Algorithm,
+}
+
+// The user who logs in at the provider next, and how the ID token is broken
+struct Login {
+    sub: String,
+    email: String,
+    email_verified: bool,
+    tamper: Tamper,
+}
+
+struct Grant {
+    login: Login,
+    nonce: String,
+    challenge: String,
+}
+
+struct Provider {
+    issuer: String,
+    key: EncodingKey,
+    other_key: EncodingKey,
+    jwk: Value,
+    next: Mutex<Option<Login>>,
+    grants: Mutex<HashMap<String, Grant>>,
+}
+
+fn generate_key() -> (RsaPrivateKey, EncodingKey) {
+    let key = RsaPrivateKey::new(&mut rand::thread_rng(), 2048).unwrap();
+    let encoding = EncodingKey::from_rsa_der(key.to_pkcs1_der().unwrap().as_bytes());
+    (key, encoding)
+}
+
+async fn discovery(State(provider): State<Arc<Provider>>) -> Json<Value> {
+    Json(json!({
+        "issuer": provider.issuer,
+        "authorization_endpoint": format!("{}/authorize", provider.issuer),
+        "token_endpoint": format!("{}/token", provider.issuer),
+        "jwks_uri": format!("{}/jwks", provider.issuer),
+    }))
+}
+
+async fn jwks(State(provider): State<Arc<Provider>>) -> Json<Value> {
+    Json(json!({ "keys": [provider.jwk] }))
+}
+
+async fn authorize(
+    State(provider): State<Arc<Provider>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    let valid = params.get("response_type").map(String::as_str) == Some("code")
+        && params.get("client_id").map(String::as_str) == Some(CLIENT_ID)
+        && params.get("redirect_uri").map(String::as_str) == Some(REDIRECT_URI)
+        && params.get("code_challenge_method").map(String::as_str) == Some("S256")
+        && params
+            .get("scope")
+            .is_some_and(|scope| scope.contains("openid"));
+    let login = provider.next.lock().unwrap().take();
+    let (true, Some(login)) = (valid, login) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let code = validation::generate_token();
+    provider.grants.lock().unwrap().insert(
+        code.clone(),
+        Grant {
+            login,
+            nonce: params["nonce"].clone(),
+            challenge: params["code_challenge"].clone(),
+        },
+    );
+    Redirect::to(&format!(
+        "{}?code={}&state={}",
+        REDIRECT_URI, code, params["state"]
+    ))
+    .into_response()
+}
+
+async fn token(
+    State(provider): State<Arc<Provider>>,
+    headers: HeaderMap,
+    Form(form): Form<HashMap<String, String>>,
+) -> Response {
+    let credentials = format!(
+        "Basic {}",
+        base64::engine::general_purpose::STANDARD
+            .encode(format!("{}:{}", CLIENT_ID, CLIENT_SECRET))
+    );
+    if headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        != Some(credentials.as_str())
+    {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    // Codes are single use
+    let Some(grant) = form
+        .get("code")
+        .and_then(|code| provider.grants.lock().unwrap().remove(code))
+    else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let verifier = form.get("code_verifier").cloned().unwrap_or_default();
+    if form.get("grant_type").map(String::as_str) != Some("authorization_code")
+        || form.get("redirect_uri").map(String::as_str) != Some(REDIRECT_URI)
+        || URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())) != grant.challenge
+    {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+    let login = grant.login;
+    let now = Utc::now().timestamp();
+    let claims = json!({
+        "iss": provider.issuer,
+        "aud": if login.tamper == Tamper::Audience { "someone-else" } else { CLIENT_ID },
+        "sub": login.sub,
+        "email": login.email,
+        "email_verified": login.email_verified,
+        "nonce": if login.tamper == Tamper::Nonce { "forged".to_string() } else { grant.nonce },
+        "iat": now,
+        "exp": now + 300,
+    });
+    // The key is published for RS256 only
+    let mut header = Header::new(if login.tamper == Tamper::Algorithm {
+        Algorithm::RS384
+    } else {
+        Algorithm::RS256
+    });
+    header.kid = Some("test".to_string());
+    let key = if login.tamper == Tamper::Key {
+        &provider.other_key
+    } else {
+        &provider.key
+    };
+    Json(json!({
+        "id_token": encode(&header, &claims, key).unwrap(),
+        "token_type": "Bearer",
+    }))
+    .into_response()
+}
+
+async fn start_provider() -> (Arc<Provider>, OidcConfig) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let issuer = format!("http://{}", listener.local_addr().unwrap());
+    let (private, key) = generate_key();
+    let (_, other_key) = generate_key();
+    let public = private.to_public_key();
+    let provider = Arc::new(Provider {
+        issuer: issuer.clone(),
+        key,
+        other_key,
+        jwk: json!({
+            "kty": "RSA",
+            "kid": "test",
+            "alg": "RS256",
+            "use": "sig",
+            "n": URL_SAFE_NO_PAD.encode(public.n().to_bytes_be()),
+            "e": URL_SAFE_NO_PAD.encode(public.e().to_bytes_be()),
+        }),
+        next: Mutex::new(None),
+        grants: Mutex::new(HashMap::new()),
+    });
+    let app = Router::new()
+        .route("/.well-known/openid-configuration", get(discovery))
+        .route("/jwks", get(jwks))
+        .route("/authorize", get(authorize))
+        .route("/token", post(token))
+        .with_state(provider.clone());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    let config = OidcConfig {
+        issuer,
+        client_id: CLIENT_ID.to_string(),
+        client_secret: CLIENT_SECRET.to_string(),
+        redirect_uri: REDIRECT_URI.to_string(),
+        frontend_redirect: "http://127.0.0.1/login".to_string(),
+    };
+    (provider, config)
+}
+
+// Follows the authorization URL like a browser would and returns the callback's code and state
+async fn authorize_login(
+    provider: &Provider,
+    config: &OidcConfig,
+    login: Login,
+) -> (String, String) {
+    *provider.next.lock().unwrap() = Some(login);
+    let url = oidc::authorization_url(config).await.unwrap();
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let response = client.get(url).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    let location = response.headers()[header::LOCATION].to_str().unwrap();
+    let callback = reqwest::Url::parse(location).unwrap();
+    let params: HashMap<_, _> = callback.query_pairs().into_owned().collect();
+    (params["code"].clone(), params["state"].clone())
+}
+
+fn login(sub: &str, email: &str) -> Login {
+    Login {
+        sub: sub.to_string(),
+        email: email.to_string(),
+        email_verified: true,
+        tamper: Tamper::None,
+    }
+}
+
+async fn log_in(provider: &Provider, config: &OidcConfig, login: Login) -> Result<String, String> {
+    let (code, state) = authorize_login(provider, config, login).await;
+    let claims = oidc::exchange(config, &code, &state)
+        .await
+        .map_err(|err| err.to_string())?;
+    oidc::resolve_user(config, &claims).map_err(|err| err.to_string())
+}
+
+fn hash_of(username: &str) -> String {
+    db::get_user_info(username).unwrap().0
+}
+
+#[tokio::test]
+async fn oidc_login() {
+    // The database lives in the working directory
+    let dir = std::env::temp_dir().join(format!("dockify-oidc-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_current_dir(&dir).unwrap();
+    db::create_db().await;
+    let (provider, config) = start_provider().await;
+
+    // A new identity provisions a verified account
+    let username = log_in(&provider, &config, login("alice-sub", "alice@gmail.com"))
+        .await
+        .unwrap();
+    assert_eq!(username, "alice");
+    assert_eq!(db::get_user_info("alice").unwrap().1, 1);
+    // and maps back to it on the next login, even if the email changed
+    let again = log_in(&provider, &config, login("alice-sub", "alice2@gmail.com")).await;
+    assert_eq!(again.unwrap(), "alice");
+
+    // An unverified local account is linked and its password no longer works
+    let hash = validation::hash_password("hunter22").unwrap();
+    db::insert_user("bob@gmail.com", "bob", &hash, false).unwrap();
+    let username = log_in(&provider, &config, login("bob-sub", "Bob@gmail.com"))
+        .await
+        .unwrap();
+    assert_eq!(username, "bob");
+    assert_eq!(db::get_user_info("bob").unwrap().1, 1);
+    assert!(validation::verify_password("hunter22", &hash_of("bob")).is_err());
+
+    // A verified local account is linked as is
+    let hash = validation::hash_password("hunter22").unwrap();
+    db::insert_user("carol@gmail.com", "carol", &hash, true).unwrap();
+    let username = log_in(&provider, &config, login("carol-sub", "carol@gmail.com")).await;
+    assert_eq!(username.unwrap(), "carol");
+    assert_eq!(hash_of("carol"), hash);
+
+    // Accounts are only matched by email, never by a username that looks like one
+    db::insert_user("mallory@gmail.com", "dave@gmail.com", &hash, true).unwrap();
+    let username = log_in(&provider, &config, login("dave-sub", "dave@gmail.com")).await;
+    assert_eq!(username.unwrap(), "dave");
+
+    // Long names stay valid usernames, even with a collision suffix
+    let long = "a".repeat(40);
+    let first = log_in(
+        &provider,
+        &config,
+        login("long-sub", &format!("{}@gmail.com", long)),
+    )
+    .await
+    .unwrap();
+    let second = log_in(
+        &provider,
+        &config,
+        login("long2-sub", &format!("{}@outlook.com", long)),
+    )
+    .await
+    .unwrap();
+    assert_ne!(first, second);
+    assert!(validation::validate_username(&first) && validation::validate_username(&second));
+
+    // Unverified provider emails are refused
+    let mut unverified = login("erin-sub", "erin@gmail.com");
+    unverified.email_verified = false;
+    assert!(log_in(&provider, &config, unverified).await.is_err());
+    assert!(db::get_user_info("erin@gmail.com").is_err());
+
+    // Tampered ID tokens are refused
+    for tamper in [
+        Tamper::Nonce,
+        Tamper::Audience,
+        Tamper::Key,
+        Tamper::Algorithm,
+    ] {
+        let mut forged = login("frank-sub", "frank@gmail.com");
+        forged.tamper = tamper;
+        assert!(log_in(&provider, &config, forged).await.is_err());
+    }
+    assert!(db::get_user_info("frank@gmail.com").is_err());
+
+    // States are single use
+    let (code, state) =
+        authorize_login(&provider, &config, login("gina-sub", "gina@gmail.com")).await;
+    assert!(oidc::exchange(&config, &code, &state).await.is_ok());
+    assert!(oidc::exchange(&config, &code, &state).await.is_err());
+    assert!(oidc::exchange(&config, &code, "unknown").await.is_err());
+
+    std::env::set_current_dir(std::env::temp_dir()).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+}